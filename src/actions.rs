@@ -4,11 +4,12 @@ use crate::{
     data::ObjectId,
     extract_ok, extract_some,
     mobs::Mob,
+    stockpiles::StockpileZone,
     tasks::{Task, TaskBundle, TaskKind, TaskNeeds},
     tilemap::TILE_SIZE,
     tilemap_data::TilemapData,
     ui::UiButton,
-    Dweller, DwellersSelected, OpenWorkstationUi,
+    Dweller, DwellersSelected, OpenStockpileZoneUi, OpenWorkstationUi,
 };
 
 const MAX_ACTIONS: usize = 2048;
@@ -20,6 +21,8 @@ pub enum ActionKind {
     Cancel,
     Task(TaskKind),
     TaskWithNeeds(TaskKind, TaskNeeds),
+    StockpileZone,
+    GraveyardZone,
 }
 
 pub fn keyboard_current_action(
@@ -102,6 +105,7 @@ pub fn terrain_pointer_up(
     q_camera: Query<(&Camera, &GlobalTransform)>,
     q_tasks: Query<(Entity, &Task)>,
     q_mobs: Query<(Entity, &Transform), With<Mob>>,
+    q_zones: Query<(Entity, &StockpileZone)>,
     mut q_dwellers: Query<(Entity, &mut Dweller, &Transform)>,
 ) {
     let event = trigger.event();
@@ -118,6 +122,31 @@ pub fn terrain_pointer_up(
         let index_min = IVec2::new(index_start.x.min(index.x), index_start.y.min(index.y));
         let index_max = IVec2::new(index_start.x.max(index.x), index_start.y.max(index.y));
 
+        // Zones are a single entity covering the whole selection
+        if matches!(current_action.kind, ActionKind::StockpileZone) {
+            let name = format!("Stockpile {}", q_zones.iter().len() + 1);
+            commands.spawn((
+                Name::new(name.clone()),
+                StockpileZone::new(name, index_min, index_max),
+            ));
+
+            debug!("Stockpile zone from {index_min:?} to {index_max:?}");
+            current_action.index_start = None;
+            return;
+        }
+
+        if matches!(current_action.kind, ActionKind::GraveyardZone) {
+            let name = format!("Graveyard {}", q_zones.iter().len() + 1);
+            commands.spawn((
+                Name::new(name.clone()),
+                StockpileZone::new_graveyard(name, index_min, index_max),
+            ));
+
+            debug!("Graveyard zone from {index_min:?} to {index_max:?}");
+            current_action.index_start = None;
+            return;
+        }
+
         let mut max_tasks = match current_action.kind {
             ActionKind::Task(TaskKind::Walk) => {
                 if dwellers_selected.list().is_empty() {
@@ -155,31 +184,28 @@ pub fn terrain_pointer_up(
                     }
 
                     // Abort if an incompatible task already exists at this position
-                    if q_tasks.iter().filter(|(_, t)| t.pos == index).any(
-                        |(entity_other_task, other_task)| match (task_kind, other_task.kind) {
-                            (TaskKind::Stockpile, TaskKind::Pickup) => {
-                                commands.entity(entity_other_task).despawn_recursive();
-                                // Stockpile task will be correctly marked TaskNeeds::Impossible below
-                                false
-                            }
-                            (TaskKind::Pickup, TaskKind::Stockpile)
-                            | (
-                                TaskKind::Smoothen,
-                                TaskKind::Stockpile
-                                | TaskKind::Pickup
-                                | TaskKind::Harvest
-                                | TaskKind::Hunt
-                                | TaskKind::Workstation { .. },
+                    if q_tasks
+                        .iter()
+                        .filter(|(_, t)| t.pos == index)
+                        .any(|(_, other_task)| {
+                            !matches!(
+                                (task_kind, other_task.kind),
+                                (
+                                    TaskKind::Smoothen,
+                                    TaskKind::Stockpile
+                                        | TaskKind::Pickup
+                                        | TaskKind::Harvest
+                                        | TaskKind::Hunt
+                                        | TaskKind::Workstation { .. },
+                                ) | (TaskKind::Hunt, _)
                             )
-                            | (TaskKind::Hunt, _) => false,
-                            _ => true,
-                        },
-                    ) {
+                        })
+                    {
                         continue;
                     }
                 }
 
-                let dweller = dwellers_selected.next();
+                let dweller = dwellers_selected.cycle();
 
                 match &current_action.kind {
                     ActionKind::Task(task_kind) => match task_kind {
@@ -249,23 +275,6 @@ pub fn terrain_pointer_up(
                             }
                         }
 
-                        TaskKind::Stockpile => {
-                            let needs = if tile.object.is_none() {
-                                TaskNeeds::AnyObject
-                            } else {
-                                TaskNeeds::Impossible
-                            };
-
-                            commands.spawn(TaskBundle::new(
-                                Task::new(index, *task_kind, dweller, &tilemap_data)
-                                    .with_priority(-1),
-                                needs,
-                            ));
-
-                            max_tasks = max_tasks.saturating_sub(1);
-                            debug!("Stockpiling task at {index:?}");
-                        }
-
                         TaskKind::Walk => {
                             commands.spawn(TaskBundle::new(
                                 Task::new(index, *task_kind, dweller, &tilemap_data)
@@ -294,6 +303,9 @@ pub fn terrain_pointer_up(
                         _ => {}
                     },
 
+                    // Handled before iterating on tiles
+                    ActionKind::StockpileZone | ActionKind::GraveyardZone => {}
+
                     ActionKind::Cancel => {
                         if let Some((entity_task, task)) =
                             q_tasks.iter().find(|(_, task)| task.pos == index)
@@ -307,11 +319,9 @@ pub fn terrain_pointer_up(
                                 }
                             }
 
-                            // if we are cancelling a Stockpile or Workstation task, mark object for pickup (if not already marked)
-                            if matches!(
-                                task.kind,
-                                TaskKind::Stockpile | TaskKind::Workstation { .. }
-                            ) && TaskKind::Pickup.is_valid_on_tile(tile)
+                            // if we are cancelling a Workstation task, mark object for pickup (if not already marked)
+                            if matches!(task.kind, TaskKind::Workstation { .. })
+                                && TaskKind::Pickup.is_valid_on_tile(tile)
                                 && !q_tasks.iter().any(|(_, task)| {
                                     task.kind == TaskKind::Pickup && task.pos == index
                                 })
@@ -321,7 +331,7 @@ pub fn terrain_pointer_up(
                                     TaskNeeds::EmptyHands,
                                 ));
 
-                                debug!("Cancelling stockpile/workstation at {index:?} and marking object for pickup");
+                                debug!("Cancelling workstation at {index:?} and marking object for pickup");
                             } else {
                                 debug!("Cancelling task at {index:?}");
                             }
@@ -341,6 +351,14 @@ pub fn terrain_pointer_up(
                                 commands.trigger_targets(OpenWorkstationUi, entity);
                                 break;
                             }
+
+                            // else open the settings of the zone under the cursor
+                            if let Some((entity, _)) =
+                                q_zones.iter().find(|(_, zone)| zone.contains(index))
+                            {
+                                commands.trigger_targets(OpenStockpileZoneUi, entity);
+                                break;
+                            }
                         }
 
                         // else select dwellers
@@ -0,0 +1,5 @@
+use dwellers::headless::{run_headless, HeadlessArgs};
+
+fn main() {
+    run_headless(&HeadlessArgs::from_args());
+}
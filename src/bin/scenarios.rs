@@ -0,0 +1,49 @@
+use std::path::{Path, PathBuf};
+
+use dwellers::scenario::{install_error_counter, scenario_paths, Scenario};
+
+const SCENARIOS_DIR: &str = "scenarios";
+
+/// Run the given scenario files, or all the scenarios of the scenarios directory
+fn main() {
+    install_error_counter();
+
+    let mut paths = std::env::args()
+        .skip(1)
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+
+    if paths.is_empty() {
+        paths = scenario_paths(Path::new(SCENARIOS_DIR))
+            .expect("Error while reading the scenarios directory");
+    }
+
+    let mut failed = 0;
+
+    for path in &paths {
+        let result = Scenario::load(path)
+            .map_err(|e| vec![e])
+            .and_then(|scenario| {
+                println!("{}: {}", path.display(), scenario.description);
+                scenario.run()
+            });
+
+        match result {
+            Ok(()) => println!("  PASS"),
+            Err(failures) => {
+                failed += 1;
+                println!("  FAIL");
+
+                for failure in failures {
+                    println!("    {failure}");
+                }
+            }
+        }
+    }
+
+    println!("{} passed, {failed} failed", paths.len() - failed);
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
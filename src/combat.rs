@@ -0,0 +1,153 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    dwellers::Dweller,
+    dwellers_needs::DwellerNeeds,
+    dwellers_skills::{DwellerSkills, Skill},
+    mobs::Mob,
+    simulation::SimulationStats,
+    tasks::{Task, TaskKind},
+    tilemap::TILE_SIZE,
+    tilemap_data::TilemapData,
+};
+
+/// Fighters attack what is on their tile or on the 8 tiles around
+const MELEE_RANGE: f32 = TILE_SIZE * 1.5;
+
+/// Loot falls this close to the dead mob when its tile is taken
+const LOOT_RADIUS: u32 = 2;
+
+/// Sprites are drawn at different depths, only the map position matters
+#[must_use]
+pub fn in_melee_range(a: &Transform, b: &Transform) -> bool {
+    a.translation.truncate().distance(b.translation.truncate()) < MELEE_RANGE
+}
+
+/// A combat round: hunters attack their mob at melee range, then the mobs still alive
+/// strike back at their hunters, or at any dweller close enough if they are hostile.
+/// Dead mobs drop their loot and teach their hunter, dead dwellers are handled by
+/// `update_dweller_deaths`.
+pub fn update_combat(
+    mut commands: Commands,
+    mut tilemap_data: ResMut<TilemapData>,
+    mut stats: ResMut<SimulationStats>,
+    mut q_mobs: Query<(Entity, &mut Mob, &Transform)>,
+    mut q_dwellers: Query<(
+        Entity,
+        &Dweller,
+        &mut DwellerNeeds,
+        &mut DwellerSkills,
+        &Transform,
+    )>,
+    q_tasks: Query<(&Task, Option<&Parent>)>,
+) {
+    // Hunter and hunted mob of each Hunt task
+    let hunts = q_tasks
+        .iter()
+        .filter(|(task, _)| task.kind == TaskKind::Hunt)
+        .filter_map(|(task, parent)| Some((task.dweller?, parent?.get())))
+        .collect::<Vec<_>>();
+
+    // Loot is not dropped on other tasks, they would become invalid
+    let tasks_pos = q_tasks
+        .iter()
+        .filter(|(task, _)| task.kind != TaskKind::Hunt)
+        .map(|(task, _)| task.pos)
+        .collect::<HashSet<_>>();
+
+    let mut dead_mobs = HashSet::new();
+
+    for (entity_dweller, entity_mob) in &hunts {
+        let Ok((_, dweller, mut needs, mut skills, dweller_transform)) =
+            q_dwellers.get_mut(*entity_dweller)
+        else {
+            continue;
+        };
+
+        let Ok((_, mut mob, mob_transform)) = q_mobs.get_mut(*entity_mob) else {
+            continue;
+        };
+
+        if needs.is_dead()
+            || dead_mobs.contains(entity_mob)
+            || !in_melee_range(dweller_transform, mob_transform)
+        {
+            continue;
+        }
+
+        mob.health = mob.health.saturating_sub(dweller.damage(&skills));
+        debug!(
+            "Dweller {} attacked mob {entity_mob}, {} health left",
+            dweller.name, mob.health
+        );
+
+        if mob.health > 0 {
+            continue;
+        }
+
+        let mob_pos = (mob_transform.translation / TILE_SIZE)
+            .truncate()
+            .as_ivec2();
+
+        let loot_pos = TilemapData::find_from_center(mob_pos, LOOT_RADIUS, |pos| {
+            !tasks_pos.contains(&pos)
+                && tilemap_data
+                    .get(pos)
+                    .is_some_and(|tile| !tile.is_blocking() && tile.room_for(mob.loot) > 0)
+        });
+
+        if let Some((loot_pos, loot_tile)) =
+            loot_pos.and_then(|pos| Some((pos, tilemap_data.get(pos)?)))
+        {
+            tilemap_data.set(loot_pos, loot_tile.add(mob.loot, 1));
+        } else {
+            debug!("Hunted mob at {mob_pos:?} but no tile around has room for its loot");
+        }
+
+        // The Hunt task is a child of the mob
+        commands.entity(*entity_mob).despawn_recursive();
+        dead_mobs.insert(*entity_mob);
+        stats.tasks_done += 1;
+
+        needs.sleep(-5);
+        needs.food(-5);
+        skills.gain_xp(Skill::Hunting, TaskKind::Hunt.work());
+
+        debug!("Hunted mob at {:?}", mob_transform.translation);
+    }
+
+    for (entity_mob, mob, mob_transform) in &q_mobs {
+        if mob.damage == 0 || dead_mobs.contains(&entity_mob) {
+            continue;
+        }
+
+        // Nearest dweller it can attack
+        let target = q_dwellers
+            .iter()
+            .filter(|(entity_dweller, _, needs, _, dweller_transform)| {
+                !needs.is_dead()
+                    && (mob.profile.behaviour.is_hostile()
+                        || hunts.contains(&(*entity_dweller, entity_mob)))
+                    && in_melee_range(dweller_transform, mob_transform)
+            })
+            .min_by(|(.., a), (.., b)| {
+                a.translation
+                    .distance_squared(mob_transform.translation)
+                    .total_cmp(&b.translation.distance_squared(mob_transform.translation))
+            })
+            .map(|(entity_dweller, ..)| entity_dweller);
+
+        let Some((_, dweller, mut needs, ..)) = target.and_then(|e| q_dwellers.get_mut(e).ok())
+        else {
+            continue;
+        };
+
+        let damage = dweller.damage_taken(mob.damage);
+        needs.health(-i32::try_from(damage).unwrap_or(i32::MAX));
+
+        debug!(
+            "Mob {entity_mob} attacked dweller {} for {damage} damage",
+            dweller.name
+        );
+    }
+}
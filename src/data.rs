@@ -1,112 +1,384 @@
-use std::sync::LazyLock;
+use std::{
+    fmt::Debug,
+    hash::Hash,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
 
 use bevy::{prelude::*, utils::hashbrown::HashMap};
 use bitcode::{Decode, Encode};
+use serde::{
+    de::{DeserializeOwned, IgnoredAny, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
 
-use crate::{enum_map, structures::StructureData, BuildResult, MobData, ObjectData, TileData};
-
-enum_map! {
-    ObjectId => ObjectData {
-        Wood = ObjectData::passable("wood"),
-        Hide = ObjectData::passable("hide"),
-        Tree = ObjectData::blocking_non_carriable("tree"),
-        Table = ObjectData::blocking("table"),
-        Stool = ObjectData::blocking("stool"),
-        Bed = ObjectData::passable("bed"),
-        Door = ObjectData::passable("door"),
-        Rock = ObjectData::passable("rock"),
-        TallGrass = ObjectData::passable_non_carriable("tall_grass"),
-        Seeds = ObjectData::passable("seeds"),
-        Farm = ObjectData::passable_non_carriable("farm"),
-        WheatPlant = ObjectData::passable_non_carriable("wheat_plant"),
-        Wheat = ObjectData::passable("wheat"),
-        Furnace = ObjectData::blocking("furnace"),
-        Bread = ObjectData::passable("bread"),
-        PalmTree = ObjectData::blocking_non_carriable("palm_tree"),
-        Cactus = ObjectData::passable_non_carriable("cactus"),
-        CopperOre = ObjectData::passable("copper_ore"),
-        CopperIngot = ObjectData::passable("copper_ingot"),
-        Forge = ObjectData::blocking("forge"),
-        Anvil = ObjectData::blocking("anvil"),
-        Grindstone = ObjectData::blocking("grindstone"),
-        Sword = ObjectData::tool("sword"),
-        Armor = ObjectData::armor("armor"),
-        Scarecrow = ObjectData::blocking("scarecrow"),
+use crate::{
+    define_ids, structures::StructureData, utils::Identifier, BuildResult, MobData, ObjectData,
+    TileData, MIN_MOVEMENT_COST,
+};
+
+/// Content definitions, read from the working directory like the other runtime assets
+const DATA_DIR: &str = "assets/data";
+
+// Only the ids used by the code are listed, the others are defined by the data files alone
+
+define_ids! {
+    ObjectId => ObjectData in objects {
+        Wood,
+        Tree,
+        Bed,
+        Door,
+        Rock,
+        TallGrass,
+        Seeds,
+        Farm,
+        WheatPlant,
+        Wheat,
+        Furnace,
+        Bread,
+        PalmTree,
+        Cactus,
+        CopperOre,
+        Scarecrow,
+        Corpse,
+        Grave,
+    }
+}
+
+define_ids! {
+    TileId => TileData in tiles {
+        GrassFloor,
+        StoneFloor,
+        DungeonFloor,
+        Bridge,
+        SandFloor,
+
+        DirtWall,
+        StoneWall,
+        DungeonWall,
+        Water,
+        WoodWall,
+    }
+}
+
+define_ids! {
+    MobId => MobData in mobs {
+        Sheep,
+        Boar,
+        Werewolf,
+    }
+}
+
+define_ids! {
+    StructureId => StructureData in structures {
+        SmallOutpost,
+    }
+}
+
+/// Names of the definitions, read ahead of them so that ids can be deserialized while loading
+pub static NAMES: LazyLock<Names> = LazyLock::new(|| {
+    let dir = Path::new(DATA_DIR);
+
+    Names {
+        objects: Names::read(dir, "objects.ron", ObjectId::BUILTIN),
+        tiles: Names::read(dir, "tiles.ron", TileId::BUILTIN),
+        mobs: Names::read(dir, "mobs.ron", MobId::BUILTIN),
+        structures: Names::read(dir, "structures.ron", StructureId::BUILTIN),
     }
+});
+
+/// Loaded on first use, `SimulationPlugin` forces it so that invalid definitions fail at startup
+pub static DEFINITIONS: LazyLock<Definitions> = LazyLock::new(|| {
+    Definitions::load(Path::new(DATA_DIR)).unwrap_or_else(|errors| {
+        panic!(
+            "Invalid definitions in {DATA_DIR}:\n  {}",
+            errors.join("\n  ")
+        )
+    })
+});
+
+/// Indexed by id
+pub struct Names {
+    pub objects: Vec<String>,
+    pub tiles: Vec<String>,
+    pub mobs: Vec<String>,
+    pub structures: Vec<String>,
 }
 
-enum_map! {
-    TileId => TileData {
-        GrassFloor = TileData::floor("grass"),
-        StoneFloor = TileData::floor("stone"),
-        DungeonFloor = TileData::floor("dungeon"),
-        Bridge = TileData::floor("bridge"),
-        SandFloor = TileData::floor("sand"),
-
-        DirtWall = TileData::wall("dirt"),
-        StoneWall = TileData::wall("stone"),
-        DungeonWall = TileData::wall("dungeon"),
-        Water = TileData::wall("water"),
-        WoodWall = TileData::wall("wood"),
+impl Names {
+    /// The builtin names, then the other keys of the file in order.
+    /// Errors are left to the loading of the definitions
+    fn read(dir: &Path, filename: &str, builtin: &[&str]) -> Vec<String> {
+        let mut names = builtin.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        let keys = std::fs::read_to_string(dir.join(filename))
+            .ok()
+            .and_then(|content| ron::from_str::<MapKeys>(&content).ok());
+
+        for key in keys.map(|keys| keys.0).unwrap_or_default() {
+            if !names.contains(&key) {
+                names.push(key);
+            }
+        }
+
+        names
     }
 }
 
-enum_map! {
-    MobId => MobData {
-        Sheep = MobData::new("sheep", 60.0, ObjectId::Hide),
-        Boar = MobData::new("boar", 50.0, ObjectId::Hide),
-        Undead = MobData::new("undead", 40.0, ObjectId::CopperIngot),
+/// Keys of a map, in the order of the file
+struct MapKeys(Vec<String>);
+
+impl<'de> Deserialize<'de> for MapKeys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeysVisitor;
+
+        impl<'de> Visitor<'de> for KeysVisitor {
+            type Value = MapKeys;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a map of definitions")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut keys = Vec::new();
+                while let Some((Identifier(key), IgnoredAny)) =
+                    map.next_entry::<Identifier, IgnoredAny>()?
+                {
+                    keys.push(key);
+                }
+                Ok(MapKeys(keys))
+            }
+        }
+
+        deserializer.deserialize_map(KeysVisitor)
     }
 }
 
-#[rustfmt::skip]
-pub const BUILD_RECIPES: &[(BuildResult, &[ObjectId])] = &[
-    (BuildResult::Tile(TileId::WoodWall), &[ObjectId::Wood]),
-    (BuildResult::Tile(TileId::DungeonWall), &[ObjectId::Rock]),
-    (BuildResult::Tile(TileId::Bridge), &[ObjectId::Wood]),
-    (BuildResult::Object(ObjectId::Table), &[ObjectId::Wood, ObjectId::Wood]),
-    (BuildResult::Object(ObjectId::Stool), &[ObjectId::Wood]),
-    (BuildResult::Object(ObjectId::Bed), &[ObjectId::Wood]),
-    (BuildResult::Object(ObjectId::Door), &[ObjectId::Wood]),
-    (BuildResult::Object(ObjectId::Farm), &[ObjectId::Seeds]),
-    (BuildResult::Object(ObjectId::Scarecrow), &[ObjectId::Wood, ObjectId::Wheat, ObjectId::Wheat]),
-    (BuildResult::Object(ObjectId::Furnace), &[ObjectId::Rock, ObjectId::Rock, ObjectId::Rock]),
-    (BuildResult::Object(ObjectId::Forge), &[ObjectId::Rock, ObjectId::Rock, ObjectId::Rock, ObjectId::CopperOre, ObjectId::CopperOre]),
-    (BuildResult::Object(ObjectId::Anvil), &[ObjectId::CopperIngot, ObjectId::CopperIngot, ObjectId::CopperIngot, ObjectId::CopperIngot]),
-    (BuildResult::Object(ObjectId::Grindstone), &[ObjectId::Rock, ObjectId::Wood]),
-];
-
-#[rustfmt::skip]
-pub static WORKSTATIONS: LazyLock<HashMap<ObjectId, (ObjectId, Vec<ObjectId>)>> =
-LazyLock::new(|| HashMap::from([
-    (ObjectId::Furnace, (ObjectId::Bread, vec![ObjectId::Wheat, ObjectId::Wood])),
-    (ObjectId::Forge, (ObjectId::CopperIngot, vec![ObjectId::CopperOre, ObjectId::CopperOre])),
-    (ObjectId::Grindstone, (ObjectId::Sword, vec![ObjectId::CopperIngot, ObjectId::CopperIngot])),
-    (ObjectId::Anvil, (ObjectId::Armor, vec![ObjectId::CopperIngot, ObjectId::CopperIngot, ObjectId::CopperIngot])),
-]));
-
-enum_map! {
-    StructureId => StructureData {
-        SmallHouse = StructureData::new(vec![
-                vec![TileId::DungeonWall.s(), TileId::DungeonWall.s(),               TileId::DungeonWall.s(),              TileId::DungeonWall.s()],
-                vec![TileId::DungeonWall.s(), TileId::DungeonFloor.s(),              TileId::DungeonFloor.i(ObjectId::Bed), TileId::DungeonWall.s()],
-                vec![TileId::DungeonWall.s(), TileId::DungeonFloor.s(),              TileId::DungeonFloor.s(),             TileId::DungeonWall.s()],
-                vec![TileId::DungeonWall.s(), TileId::DungeonFloor.i(ObjectId::Door), TileId::DungeonWall.s(),              TileId::DungeonWall.s()],
-            ],
-            vec![((1,1), MobId::Sheep)],
-        ),
-
-        SmallOutpost = StructureData::new(vec![
-                vec![None,                 None,                     TileId::WoodWall.s(),     TileId::WoodWall.s(),     TileId::WoodWall.s(),                       TileId::WoodWall.s(),     TileId::WoodWall.s()],
-                vec![None,                 TileId::WoodWall.s(),     TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(),                   TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::WoodWall.s()],
-                vec![TileId::WoodWall.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(),                   TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::WoodWall.s()],
-                vec![TileId::WoodWall.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(),                   TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::WoodWall.s()],
-                vec![TileId::WoodWall.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(),                   TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::WoodWall.s()],
-                vec![TileId::WoodWall.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(),                   TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::WoodWall.s()],
-                vec![None,                 TileId::WoodWall.s(),     TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::DungeonFloor.s(),                   TileId::DungeonFloor.s(), TileId::DungeonFloor.s(), TileId::WoodWall.s()],
-                vec![None,                 None,                     TileId::WoodWall.s(),     TileId::WoodWall.s(),     TileId::DungeonFloor.i(ObjectId::Door),     TileId::WoodWall.s(),     TileId::WoodWall.s()],
-            ],
-            vec![((4,4), MobId::Undead)],
-        ),
+pub struct Definitions {
+    /// Indexed by id
+    pub objects: Vec<ObjectData>,
+    pub tiles: Vec<TileData>,
+    pub mobs: Vec<MobData>,
+    pub structures: Vec<StructureData>,
+
+    pub build_recipes: Vec<(BuildResult, Vec<ObjectId>)>,
+    /// Recipes offered by each workstation object, bills refer to them by index
+    pub workstations: HashMap<ObjectId, Vec<Recipe>>,
+}
+
+#[derive(Deserialize)]
+pub struct Recipe {
+    pub output: ObjectId,
+    pub ingredients: Vec<ObjectId>,
+}
+
+impl Definitions {
+    #[must_use]
+    pub fn recipe(&self, workstation: ObjectId, index: usize) -> Option<&Recipe> {
+        self.workstations.get(&workstation)?.get(index)
+    }
+
+    /// Read and validate all the definitions
+    ///
+    /// # Errors
+    /// Every unreadable file and invalid definition found
+    pub fn load(dir: &Path) -> Result<Self, Vec<String>> {
+        let mut loader = Loader {
+            dir,
+            errors: Vec::new(),
+        };
+
+        let objects = loader.read_by_id("objects.ron", ObjectId::all());
+        let tiles = loader.read_by_id("tiles.ron", TileId::all());
+        let mobs = loader.read_by_id("mobs.ron", MobId::all());
+        let structures = loader.read_by_id("structures.ron", StructureId::all());
+        let build_recipes = loader.read("build_recipes.ron");
+        let workstations = loader.read("workstations.ron");
+
+        let (
+            Some(objects),
+            Some(tiles),
+            Some(mobs),
+            Some(structures),
+            Some(build_recipes),
+            Some(workstations),
+        ) = (
+            objects,
+            tiles,
+            mobs,
+            structures,
+            build_recipes,
+            workstations,
+        )
+        else {
+            return Err(loader.errors);
+        };
+
+        let definitions = Definitions {
+            objects,
+            tiles,
+            mobs,
+            structures,
+            build_recipes,
+            workstations,
+        };
+        definitions.validate(&mut loader);
+
+        if loader.errors.is_empty() {
+            Ok(definitions)
+        } else {
+            Err(loader.errors)
+        }
+    }
+
+    fn validate(&self, loader: &mut Loader) {
+        for (id, object) in ObjectId::all().zip(&self.objects) {
+            loader.check_sprite("objects.ron", &id, &object.sprite_path());
+
+            if object.max_stack() == 0 {
+                loader
+                    .errors
+                    .push(format!("objects.ron: {id:?} stack must be at least 1"));
+            }
+        }
+
+        for (id, tile) in TileId::all().zip(&self.tiles) {
+            loader.check_sprite("tiles.ron", &id, &tile.sprite_path());
+
+            if !tile.is_wall() && tile.movement_cost() < MIN_MOVEMENT_COST {
+                loader.errors.push(format!(
+                    "tiles.ron: {id:?} movement cost {} is lower than {MIN_MOVEMENT_COST}",
+                    tile.movement_cost()
+                ));
+            }
+        }
+
+        for (id, mob) in MobId::all().zip(&self.mobs) {
+            loader.check_sprite("mobs.ron", &id, &mob.sprite_path());
+
+            if mob.health() == 0 {
+                loader
+                    .errors
+                    .push(format!("mobs.ron: {id:?} health must be at least 1"));
+            }
+        }
+
+        for (result, ingredients) in &self.build_recipes {
+            loader.check_ingredients("build_recipes.ron", result, ingredients, &self.objects);
+        }
+
+        for (workstation, recipes) in &self.workstations {
+            if recipes.is_empty() {
+                loader
+                    .errors
+                    .push(format!("workstations.ron: {workstation:?} has no recipes"));
+            }
+
+            if !self
+                .build_recipes
+                .iter()
+                .any(|(result, _)| *result == BuildResult::Object(*workstation))
+            {
+                loader.errors.push(format!(
+                    "workstations.ron: {workstation:?} has no build recipe"
+                ));
+            }
+
+            for Recipe {
+                output,
+                ingredients,
+            } in recipes
+            {
+                loader.check_ingredients("workstations.ron", output, ingredients, &self.objects);
+
+                if !self.objects[output.index()].is_carriable() {
+                    loader.errors.push(format!(
+                        "workstations.ron: {workstation:?} output {output:?} is not carriable"
+                    ));
+                }
+            }
+        }
+    }
+}
+
+struct Loader<'a> {
+    dir: &'a Path,
+    errors: Vec<String>,
+}
+
+impl Loader<'_> {
+    fn read<T: DeserializeOwned>(&mut self, filename: &str) -> Option<T> {
+        let path = self.dir.join(filename);
+
+        let result = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| ron::from_str(&content).map_err(|e| e.to_string()));
+
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.errors.push(format!("{filename}: {e}"));
+                None
+            }
+        }
+    }
+
+    /// Read a map from id to data, ordered like the ids. Every id must be defined
+    fn read_by_id<Id: Copy + Eq + Hash + Debug + DeserializeOwned, T: DeserializeOwned>(
+        &mut self,
+        filename: &str,
+        ids: impl Iterator<Item = Id>,
+    ) -> Option<Vec<T>> {
+        let ids = ids.collect::<Vec<_>>();
+        let mut definitions = self.read::<HashMap<Id, T>>(filename)?;
+        let mut ordered = Vec::with_capacity(ids.len());
+
+        for id in &ids {
+            match definitions.remove(id) {
+                Some(definition) => ordered.push(definition),
+                None => self
+                    .errors
+                    .push(format!("{filename}: {id:?} is not defined")),
+            }
+        }
+
+        (ordered.len() == ids.len()).then_some(ordered)
+    }
+
+    fn check_sprite(&mut self, filename: &str, id: &impl Debug, sprite_path: &str) {
+        let path = self
+            .dir
+            .parent()
+            .map_or_else(PathBuf::new, Path::to_path_buf)
+            .join(sprite_path);
+
+        if !path.is_file() {
+            self.errors.push(format!(
+                "{filename}: {id:?} sprite {} does not exist",
+                path.display()
+            ));
+        }
+    }
+
+    fn check_ingredients(
+        &mut self,
+        filename: &str,
+        result: &impl Debug,
+        ingredients: &[ObjectId],
+        objects: &[ObjectData],
+    ) {
+        if ingredients.is_empty() {
+            self.errors
+                .push(format!("{filename}: {result:?} has no ingredients"));
+        }
+
+        // Dwellers bring ingredients in their hands
+        for ingredient in ingredients {
+            if !objects[ingredient.index()].is_carriable() {
+                self.errors.push(format!(
+                    "{filename}: {result:?} ingredient {ingredient:?} is not carriable"
+                ));
+            }
+        }
     }
 }
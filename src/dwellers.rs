@@ -9,11 +9,16 @@ use rand::{seq::IndexedRandom, Rng};
 
 use crate::{
     data::ObjectId,
+    dwellers_inventory::Inventory,
     dwellers_needs::DwellerNeeds,
+    dwellers_pathfinding::PendingPath,
+    dwellers_skills::{DwellerSkills, Skill},
     random_text::{generate_word, NAMES},
+    simulation::SimulationRng,
     tasks::{BuildResult, Task, TaskCompletionEvent, TaskKind, TaskNeeds},
     tilemap::TILE_SIZE,
     tilemap_data::TilemapData,
+    tiles::TilePlaced,
     LoadChunk, SpriteLoader, UnloadChunk, CHUNK_SIZE,
 };
 
@@ -22,51 +27,135 @@ const LOAD_CHUNKS_RADIUS: i32 = 1;
 const SPEED: f32 = 120.0;
 const Z_INDEX: f32 = 10.0;
 
+/// Damage of an attack without a weapon
+const DAMAGE: u32 = 25;
+/// Damage added to attacks by each Hunting level
+const DAMAGE_PER_HUNTING_LEVEL: u32 = 2;
+/// Tenths of its damage or armor value added to a tool or an armor by each quality level
+const BONUS_PER_QUALITY_LEVEL: u32 = 1;
+
+const WORK_PRIORITY_LOWEST: u8 = 4;
+const WORK_PRIORITY_DEFAULT: u8 = 3;
+
 #[derive(Event)]
 pub struct SpawnDwellersOnChunk(pub IVec2);
 
+#[derive(PartialEq, Eq, Clone, Copy, Reflect, Debug)]
+pub enum WorkCategory {
+    Skill(Skill),
+    Hauling,
+}
+
+impl WorkCategory {
+    pub const ALL: [WorkCategory; Skill::ALL.len() + 1] = {
+        let mut all = [WorkCategory::Hauling; Skill::ALL.len() + 1];
+        let mut i = 0;
+        while i < Skill::ALL.len() {
+            all[i] = WorkCategory::Skill(Skill::ALL[i]);
+            i += 1;
+        }
+        all
+    };
+
+    /// Work category of a task, None if the task can always be done (Walk, needs).
+    /// Pickups are only categorized by the task spawning them, see `Task::category`
+    #[must_use]
+    pub fn for_task(task_kind: TaskKind, tile: TilePlaced) -> Option<Self> {
+        if task_kind == TaskKind::Stockpile {
+            return Some(WorkCategory::Hauling);
+        }
+
+        Skill::for_task(task_kind, tile).map(WorkCategory::Skill)
+    }
+
+    #[inline]
+    fn index(self) -> usize {
+        match self {
+            WorkCategory::Skill(skill) => skill.index(),
+            WorkCategory::Hauling => Skill::ALL.len(),
+        }
+    }
+}
+
+impl std::fmt::Display for WorkCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkCategory::Skill(skill) => write!(f, "{skill:?}"),
+            WorkCategory::Hauling => write!(f, "Hauling"),
+        }
+    }
+}
+
+/// Priority of each work category for a dweller, from 1 (highest) to 4 (lowest), 0 is disabled
+#[derive(Reflect, Clone, Debug)]
+pub struct WorkPriorities {
+    priorities: [u8; WorkCategory::ALL.len()],
+}
+
+impl Default for WorkPriorities {
+    fn default() -> Self {
+        Self {
+            priorities: [WORK_PRIORITY_DEFAULT; WorkCategory::ALL.len()],
+        }
+    }
+}
+
+impl WorkPriorities {
+    #[must_use]
+    pub fn get(&self, category: WorkCategory) -> Option<u8> {
+        match self.priorities[category.index()] {
+            0 => None,
+            priority => Some(priority),
+        }
+    }
+
+    /// Cycle through priorities: 1, 2, 3, 4, disabled
+    pub fn cycle(&mut self, category: WorkCategory) {
+        let priority = &mut self.priorities[category.index()];
+
+        *priority = match *priority {
+            WORK_PRIORITY_LOWEST => 0,
+            priority => priority + 1,
+        };
+    }
+}
+
 #[derive(Component, Reflect, Default)]
 #[reflect(Component, Default)]
+#[require(DwellerSkills)]
 pub struct Dweller {
     pub name: String,
     pub move_queue: Vec<IVec2>, // next move is at the end
-    pub object: Option<ObjectId>,
-    pub tool: Option<ObjectId>,
-    pub armor: Option<ObjectId>,
+    pub inventory: Inventory,
+    /// Tool held and its quality
+    pub tool: Option<(ObjectId, u8)>,
+    /// Armor worn and its quality
+    pub armor: Option<(ObjectId, u8)>,
+    pub work_priorities: WorkPriorities,
 }
 
 impl Dweller {
+    #[must_use]
     pub fn can_do(&self, task_kind: TaskKind, task_needs: &TaskNeeds) -> bool {
-        match task_kind {
-            TaskKind::Workstation { amount: 0 } => return false,
-            _ => {}
-        }
-
         match task_needs {
             TaskNeeds::Nothing => {}
             TaskNeeds::EmptyHands => {
-                if self.object.is_some() {
+                if !self.inventory.has_free_slot() {
                     return false;
                 }
             }
-            TaskNeeds::Objects(objects) => match self.object {
-                None => return false,
-                Some(dweller_object) => {
-                    if !objects.iter().any(|object| *object == dweller_object)
-                        && !matches!(
-                            task_kind,
-                            TaskKind::Build {
-                                result: BuildResult::Object(build_object),
-                                ..
-                            } if build_object == dweller_object
-                        )
-                    {
-                        return false;
-                    }
-                }
-            },
-            TaskNeeds::AnyObject => {
-                if self.object.is_none() {
+            TaskNeeds::Objects(objects) => {
+                if !objects
+                    .iter()
+                    .any(|object| self.inventory.contains(*object))
+                    && !matches!(
+                        task_kind,
+                        TaskKind::Build {
+                            result: BuildResult::Object(build_object),
+                            ..
+                        } if self.inventory.contains(build_object)
+                    )
+                {
                     return false;
                 }
             }
@@ -77,6 +166,37 @@ impl Dweller {
 
         true
     }
+
+    /// Forget the planned path but finish the step in progress, so that the dweller stops on a tile
+    pub fn stop_after_step(&mut self) {
+        let steps = self.move_queue.len();
+        self.move_queue.drain(..steps.saturating_sub(1));
+    }
+
+    /// Damage of an attack, with the tool held and the Hunting skill
+    #[must_use]
+    pub fn damage(&self, skills: &DwellerSkills) -> u32 {
+        DAMAGE
+            + self.tool.map_or(0, |(tool, quality)| {
+                with_quality(tool.data().damage(), quality)
+            })
+            + skills.level(Skill::Hunting) * DAMAGE_PER_HUNTING_LEVEL
+    }
+
+    /// Damage actually taken from an attack, reduced by the armor worn
+    #[must_use]
+    pub fn damage_taken(&self, damage: u32) -> u32 {
+        damage
+            .saturating_sub(self.armor.map_or(0, |(armor, quality)| {
+                with_quality(armor.data().armor(), quality)
+            }))
+            .max(1)
+    }
+}
+
+/// Damage or armor value of an equipment, raised by its quality
+fn with_quality(value: u32, quality: u8) -> u32 {
+    value * (10 + u32::from(quality) * BONUS_PER_QUALITY_LEVEL) / 10
 }
 
 #[derive(Resource, Default)]
@@ -86,7 +206,8 @@ pub struct DwellersSelected {
 }
 
 impl DwellersSelected {
-    pub fn next(&mut self) -> Option<Entity> {
+    /// Selected dwellers in turn, None if none are selected
+    pub fn cycle(&mut self) -> Option<Entity> {
         if self.list.is_empty() {
             return None;
         }
@@ -108,6 +229,12 @@ impl DwellersSelected {
         }
     }
 
+    pub fn remove(&mut self, entity: Entity) {
+        self.list.retain(|e| *e != entity);
+        self.i = 0;
+    }
+
+    #[must_use]
     pub fn list(&self) -> &[Entity] {
         &self.list
     }
@@ -117,6 +244,7 @@ pub fn spawn_dwellers(
     mut commands: Commands,
     tilemap_data: Res<TilemapData>,
     mut ev_spawn: EventReader<SpawnDwellersOnChunk>,
+    mut rng: ResMut<SimulationRng>,
 ) {
     for SpawnDwellersOnChunk(chunk_index) in ev_spawn.read() {
         let Some(spawn_pos) = TilemapData::find_from_center_chunk_size(
@@ -143,17 +271,19 @@ pub fn spawn_dwellers(
         };
 
         let nb_dwellers = 10;
-        let mut rng = rand::rng();
 
         for _ in 0..nb_dwellers {
-            let mut name = generate_word(&NAMES, &mut rng);
-            name.get_mut(0..1).unwrap().make_ascii_uppercase();
+            let mut name = generate_word(&NAMES, &mut **rng);
+            if let Some(initial) = name.get_mut(0..1) {
+                initial.make_ascii_uppercase();
+            }
 
             let sprite_i = rng.random_range(1..=4);
 
             commands.spawn((
                 Dweller { name, ..default() },
                 DwellerNeeds::default(),
+                DwellerSkills::random(&mut **rng),
                 SpriteLoader {
                     texture_path: format!("sprites/dweller{sprite_i}.png"),
                 },
@@ -187,11 +317,15 @@ pub fn spawn_dwellers_name(
 }
 
 pub fn update_dwellers(
-    mut q_dwellers: Query<(Entity, &mut Dweller, &Transform)>,
+    mut commands: Commands,
+    mut q_dwellers: Query<(Entity, &mut Dweller, &Transform), Without<PendingPath>>,
     tilemap_data: Res<TilemapData>,
     mut q_tasks: Query<(Entity, &mut Task, &TaskNeeds)>,
     mut ev_task_completion: EventWriter<TaskCompletionEvent>,
+    mut rng: ResMut<SimulationRng>,
 ) {
+    let mut snapshot = None;
+
     for (entity, mut dweller, transform) in &mut q_dwellers {
         if !dweller.move_queue.is_empty() {
             continue;
@@ -211,14 +345,16 @@ pub fn update_dwellers(
             });
 
         if let Some((entity_task, mut task, _)) = task {
-            if task.reachable_positions.iter().any(|pos| *pos == index) {
+            if task.reachable_positions.contains(&index) {
                 // Reached task location
                 ev_task_completion.send(TaskCompletionEvent { task: entity_task });
             } else {
                 // Task moved, try to pathfind again
-                if let Some(path) = task.pathfind(index, &tilemap_data) {
-                    debug!("Dweller {} can re-pathfind to {:?}", dweller.name, task);
-                    dweller.move_queue = path.0;
+                if let Some(pending_path) =
+                    PendingPath::request(entity_task, &task, index, &tilemap_data, &mut snapshot)
+                {
+                    debug!("Dweller {} re-pathfinds to {:?}", dweller.name, task);
+                    commands.entity(entity).insert(pending_path);
                 } else {
                     info!("Dweller {} gives up {:?}", dweller.name, task);
                     task.dweller = None;
@@ -229,12 +365,10 @@ pub fn update_dwellers(
         }
 
         // Else, wander around
-        let mut rng = rand::rng();
-
         if rng.random_bool(0.2) {
             let directions = tilemap_data.non_blocking_neighbours_pos(index, true);
 
-            if let Some(direction) = directions.choose(&mut rng) {
+            if let Some(direction) = directions.choose(&mut **rng) {
                 dweller.move_queue.push(*direction);
             }
         }
@@ -242,6 +376,7 @@ pub fn update_dwellers(
 }
 
 pub fn assign_tasks_to_dwellers(
+    mut commands: Commands,
     tilemap_data: Res<TilemapData>,
     mut q_dwellers: Query<(Entity, &mut Dweller, &Transform)>,
     mut q_tasks: Query<(Entity, &mut Task, &TaskNeeds)>,
@@ -278,33 +413,139 @@ pub fn assign_tasks_to_dwellers(
     // Compute all distances
     let mut heap = BinaryHeap::new();
 
-    for (dweller_i, (_, _, dweller_pos)) in dwellers.iter().enumerate() {
-        for (task_i, (_, task, _)) in tasks.iter().enumerate() {
+    let tasks_categories = tasks
+        .iter()
+        .map(|(_, task, _)| {
+            task.category.or_else(|| {
+                tilemap_data
+                    .get(task.pos)
+                    .and_then(|tile| WorkCategory::for_task(task.kind, tile))
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Objects offered by Pickup tasks
+    let tasks_pickups = tasks
+        .iter()
+        .map(|(_, task, _)| {
+            if task.kind == TaskKind::Pickup {
+                tilemap_data.get(task.pos).and_then(|tile| tile.object)
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    for (dweller_i, (_, dweller, dweller_pos)) in dwellers.iter().enumerate() {
+        // Objects still missing for the tasks the dweller carries objects for,
+        // they are fetched first so that everything is brought in one trip
+        let mut missing = HashSet::new();
+        if dweller.inventory.has_free_slot() {
+            for (_, _, task_needs) in &tasks {
+                if let TaskNeeds::Objects(objects) = task_needs {
+                    if objects
+                        .iter()
+                        .any(|object| dweller.inventory.contains(*object))
+                    {
+                        missing.extend(
+                            objects
+                                .iter()
+                                .filter(|object| !dweller.inventory.contains(**object))
+                                .copied(),
+                        );
+                    }
+                }
+            }
+        }
+
+        let can_fetch = |object: ObjectId| {
+            tasks
+                .iter()
+                .zip(&tasks_pickups)
+                .any(|((_, task, _), pickup)| {
+                    *pickup == Some(object)
+                        && task
+                            .reachable_positions
+                            .iter()
+                            .any(|pos| tilemap_data.may_be_connected(*dweller_pos, *pos))
+                })
+        };
+
+        for (task_i, (_, task, task_needs)) in tasks.iter().enumerate() {
+            // Do not deliver a part of the objects if the rest can be fetched
+            if let TaskNeeds::Objects(objects) = task_needs {
+                if !dweller.inventory.covers(objects)
+                    && objects
+                        .iter()
+                        .any(|object| dweller.inventory.contains(*object))
+                    && objects
+                        .iter()
+                        .any(|object| missing.contains(object) && can_fetch(*object))
+                {
+                    continue;
+                }
+            }
+
+            let fetching = tasks_pickups[task_i].is_some_and(|object| missing.contains(&object));
+
+            // Skip tasks in another connected region, they can't be reached
+            if !task
+                .reachable_positions
+                .iter()
+                .any(|pos| tilemap_data.may_be_connected(*dweller_pos, *pos))
+            {
+                continue;
+            }
+
+            // Skip work categories disabled for this dweller
+            let work_priority = match tasks_categories[task_i] {
+                Some(category) => match dweller.work_priorities.get(category) {
+                    Some(work_priority) => work_priority,
+                    None => continue,
+                },
+                None => 1,
+            };
+
             let distance = (dweller_pos.x - task.pos.x).abs() + (dweller_pos.y - task.pos.y).abs();
-            heap.push((task.priority, -distance, dweller_i, task_i));
+            // The dweller's work priorities come first, then the tasks priorities
+            heap.push((
+                -i32::from(work_priority),
+                task.priority,
+                fetching,
+                -distance,
+                dweller_i,
+                task_i,
+            ));
         }
     }
 
     let mut assigned_dwellers = HashSet::new();
     let mut assigned_tasks = HashSet::new();
+    let mut snapshot = None;
 
     // Process the heap until it is empty or all tasks/dwellers are assigned
-    while let Some((_, _, dweller_i, task_i)) = heap.pop() {
+    while let Some((_, _, _, _, dweller_i, task_i)) = heap.pop() {
         if assigned_dwellers.contains(&dweller_i) || assigned_tasks.contains(&task_i) {
             continue;
         }
 
-        let (_, task, task_needs) = &mut tasks[task_i];
+        let (task_entity, task, task_needs) = &mut tasks[task_i];
         let (dweller_entity, dweller, dweller_pos) = &mut dwellers[dweller_i];
 
         if !dweller.can_do(task.kind, task_needs) {
             continue;
         }
 
-        // Try pathfinding to task
-        if let Some(path) = task.pathfind(*dweller_pos, &tilemap_data) {
+        // Reserve the task while the path is computed off the main thread
+        if let Some(pending_path) = PendingPath::request(
+            *task_entity,
+            task,
+            *dweller_pos,
+            &tilemap_data,
+            &mut snapshot,
+        ) {
             task.dweller = Some(*dweller_entity);
-            dweller.move_queue = path.0;
+            commands.entity(*dweller_entity).insert(pending_path);
 
             assigned_dwellers.insert(dweller_i);
             assigned_tasks.insert(task_i);
@@ -317,9 +558,15 @@ pub fn assign_tasks_to_dwellers(
 
 pub fn update_dwellers_movement(
     time: Res<Time>,
-    mut q_dwellers: Query<(&mut Dweller, &DwellerNeeds, &mut Transform, &mut Sprite)>,
+    tilemap_data: Res<TilemapData>,
+    mut q_dwellers: Query<(
+        &mut Dweller,
+        &DwellerNeeds,
+        &mut Transform,
+        Option<&mut Sprite>,
+    )>,
 ) {
-    for (mut dweller, needs, mut transform, mut sprite) in &mut q_dwellers {
+    for (mut dweller, needs, mut transform, sprite) in &mut q_dwellers {
         // Move to next position in queue
 
         if let Some(next_move) = dweller.move_queue.last() {
@@ -330,7 +577,12 @@ pub fn update_dwellers_movement(
 
             let direction = target - transform.translation.truncate();
 
-            let speed = SPEED * needs.speed_ratio() * time.delta_secs();
+            let speed = SPEED
+                * needs.speed_ratio()
+                * tilemap_data
+                    .get(*next_move)
+                    .map_or(1.0, TilePlaced::speed_ratio)
+                * time.delta_secs();
 
             if direction.length() < speed {
                 transform.translation.x = target.x;
@@ -341,7 +593,10 @@ pub fn update_dwellers_movement(
                 transform.translation.x += dir.x * speed;
                 transform.translation.y += dir.y * speed;
 
-                sprite.flip_x = dir.x < 0.0;
+                // No sprite when running headless
+                if let Some(mut sprite) = sprite {
+                    sprite.flip_x = dir.x < 0.0;
+                }
             }
         }
     }
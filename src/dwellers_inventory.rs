@@ -0,0 +1,186 @@
+use bevy::prelude::*;
+
+use crate::data::ObjectId;
+
+/// Stacks a dweller can carry at once, besides the tool and the armor
+pub const INVENTORY_SLOTS: usize = 3;
+
+/// Units of an object fitting in a single slot
+pub const SLOT_CAPACITY: u8 = 10;
+
+/// Food kept for later, in its own pocket so that hauling does not store it
+pub const RATION: ObjectId = ObjectId::Bread;
+
+/// Objects carried by a dweller, each slot holds a stack of a single object and quality
+#[derive(Reflect, Clone, Default, Debug)]
+pub struct Inventory {
+    /// Object, units and quality of each stack
+    slots: Vec<(ObjectId, u8, u8)>,
+    ration: bool,
+}
+
+impl Inventory {
+    #[must_use]
+    pub fn new(slots: Vec<(ObjectId, u8)>) -> Self {
+        let mut inventory = Self::default();
+        for (object, count) in slots {
+            inventory.add(object, count);
+        }
+        inventory
+    }
+
+    /// Units of `object` a single slot can hold
+    #[must_use]
+    pub fn slot_capacity(object: ObjectId) -> u8 {
+        SLOT_CAPACITY.min(object.data().max_stack())
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn has_free_slot(&self) -> bool {
+        self.slots.len() < INVENTORY_SLOTS
+    }
+
+    /// Carried stacks, in the order they were picked up
+    pub fn stacks(&self) -> impl Iterator<Item = (ObjectId, u8)> + '_ {
+        self.slots
+            .iter()
+            .map(|(object, count, _)| (*object, *count))
+    }
+
+    /// Carried stacks with their quality, in the order they were picked up
+    pub fn stacks_with_quality(&self) -> impl Iterator<Item = (ObjectId, u8, u8)> + '_ {
+        self.slots.iter().copied()
+    }
+
+    #[must_use]
+    pub fn count(&self, object: ObjectId) -> u8 {
+        self.slots
+            .iter()
+            .filter(|(o, _, _)| *o == object)
+            .map(|(_, count, _)| *count)
+            .sum()
+    }
+
+    /// Quality of the next `count` units of `object` removed by `take`, the lowest of them
+    #[must_use]
+    pub fn quality(&self, object: ObjectId, count: u8) -> u8 {
+        let mut left = count;
+
+        self.slots
+            .iter()
+            .rev()
+            .filter(|(o, _, _)| *o == object)
+            .take_while(|(_, stack, _)| {
+                let counted = left > 0;
+                left = left.saturating_sub(*stack);
+                counted
+            })
+            .map(|(_, _, quality)| *quality)
+            .min()
+            .unwrap_or(0)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn contains(&self, object: ObjectId) -> bool {
+        self.count(object) > 0
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn has_ration(&self) -> bool {
+        self.ration
+    }
+
+    /// Keep a ration for later, returns false if one is already kept
+    pub fn keep_ration(&mut self) -> bool {
+        !std::mem::replace(&mut self.ration, true)
+    }
+
+    /// Returns false if no ration is kept
+    pub fn take_ration(&mut self) -> bool {
+        std::mem::replace(&mut self.ration, false)
+    }
+
+    /// Carried units of each object, the ration included
+    pub fn totals(&self) -> impl Iterator<Item = (ObjectId, u8)> + '_ {
+        self.stacks().chain(self.ration.then_some((RATION, 1)))
+    }
+
+    /// Units of `object` that can still be carried
+    #[must_use]
+    pub fn room_for(&self, object: ObjectId) -> u8 {
+        let capacity = Self::slot_capacity(object);
+        let in_stacks = self
+            .slots
+            .iter()
+            .filter(|(o, _, _)| *o == object)
+            .map(|(_, count, _)| capacity.saturating_sub(*count))
+            .sum::<u8>();
+        let free_slots =
+            u8::try_from(INVENTORY_SLOTS.saturating_sub(self.slots.len())).unwrap_or(0);
+
+        in_stacks.saturating_add(free_slots.saturating_mul(capacity))
+    }
+
+    /// Add up to `count` units with no quality, filling existing stacks first, returns the number added
+    pub fn add(&mut self, object: ObjectId, count: u8) -> u8 {
+        self.add_with_quality(object, count, 0)
+    }
+
+    /// Add up to `count` units of a quality, filling existing stacks of the same quality first,
+    /// returns the number added
+    pub fn add_with_quality(&mut self, object: ObjectId, count: u8, quality: u8) -> u8 {
+        let capacity = Self::slot_capacity(object);
+        let mut left = count;
+
+        for (o, stack, q) in &mut self.slots {
+            if *o == object && *q == quality && left > 0 {
+                let added = left.min(capacity.saturating_sub(*stack));
+                *stack += added;
+                left -= added;
+            }
+        }
+
+        while left > 0 && self.has_free_slot() {
+            let added = left.min(capacity);
+            self.slots.push((object, added, quality));
+            left -= added;
+        }
+
+        count - left
+    }
+
+    /// Remove up to `count` units, emptying the last stacks first, returns the number removed
+    pub fn take(&mut self, object: ObjectId, count: u8) -> u8 {
+        let mut left = count;
+
+        for (o, stack, _) in self.slots.iter_mut().rev() {
+            if *o == object && left > 0 {
+                let taken = left.min(*stack);
+                *stack -= taken;
+                left -= taken;
+            }
+        }
+
+        self.slots.retain(|(_, stack, _)| *stack > 0);
+
+        count - left
+    }
+
+    /// The carried objects cover all of `objects`, counting repeated objects
+    #[must_use]
+    pub fn covers(&self, objects: &[ObjectId]) -> bool {
+        objects.iter().all(|object| {
+            let needed = objects.iter().filter(|o| *o == object).count();
+            usize::from(self.count(*object)) >= needed
+        })
+    }
+}
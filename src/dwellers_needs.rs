@@ -1,7 +1,11 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashSet};
 
 use crate::{
     data::ObjectId,
+    dwellers::{Dweller, DwellersSelected},
+    dwellers_inventory::RATION,
+    notifications::Notification,
+    reservations::{Reservation, Reservations},
     tasks::{Task, TaskBundle, TaskKind, TaskNeeds},
     tilemap::TILE_SIZE,
     tilemap_data::TilemapData,
@@ -31,6 +35,19 @@ impl Default for DwellerNeeds {
 }
 
 impl DwellerNeeds {
+    /// Needs are capped to their maximum
+    #[must_use]
+    pub fn new(health: u32, food: u32, sleep: u32) -> Self {
+        let mut needs = Self {
+            health: health.min(NEEDS_MAX),
+            food: food.min(NEEDS_MAX),
+            sleep: sleep.min(NEEDS_MAX),
+            ..default()
+        };
+        needs.compute_speed_ratio();
+        needs
+    }
+
     pub fn health(&mut self, x: i32) {
         self.health = self.health.saturating_add_signed(x).min(NEEDS_MAX);
         self.compute_speed_ratio();
@@ -70,24 +87,33 @@ impl DwellerNeeds {
     }
 
     #[inline]
+    #[must_use]
     pub fn speed_ratio(&self) -> f32 {
         self.cached_speed_ratio
     }
 
     #[inline]
+    #[must_use]
     pub fn is_fully_rested(&self) -> bool {
         self.sleep == NEEDS_MAX
     }
+
+    #[inline]
+    #[must_use]
+    pub fn is_dead(&self) -> bool {
+        self.health == 0
+    }
 }
 
 pub fn update_dweller_needs(
     mut commands: Commands,
     tilemap_data: Res<TilemapData>,
-    mut q_needs: Query<(Entity, &mut DwellerNeeds, &Transform)>,
+    mut reservations: ResMut<Reservations>,
+    mut q_needs: Query<(Entity, &mut Dweller, &mut DwellerNeeds, &Transform)>,
     q_tasks: Query<&Task>,
 ) {
-    for (entity, mut needs, transform) in &mut q_needs {
-        if needs.health == 0 {
+    for (entity, mut dweller, mut needs, transform) in &mut q_needs {
+        if needs.is_dead() {
             continue;
         }
 
@@ -106,45 +132,138 @@ pub fn update_dweller_needs(
         );
 
         if needs.food < NEEDS_MAX / 2 {
-            if let Some(pos) = TilemapData::find_from_center_chunk_size(pos, |index| {
-                matches!(
-                    tilemap_data.get(index),
-                    Some(TilePlaced {
-                        object: Some(ObjectId::Bread),
-                        ..
-                    })
-                ) && !q_tasks
-                    .iter()
-                    .filter(|t| {
-                        !(matches!(t.kind, TaskKind::Pickup | TaskKind::Stockpile)
-                            && t.dweller.is_none())
-                    })
-                    .any(|t| t.pos == index)
-            }) {
-                commands.spawn(TaskBundle::new(
-                    Task::new(pos, TaskKind::UseToSatisfyNeed, Some(entity), &tilemap_data)
-                        .with_priority(1),
-                    TaskNeeds::Nothing,
-                ));
+            // Eat the carried ration first
+            if dweller.inventory.take_ration() {
+                needs.food(500);
+
+                debug!("Dweller {} ate a ration {:?}", dweller.name, needs);
+            } else if let Some(pos) =
+                find_unclaimed(&tilemap_data, &reservations, pos, ObjectId::Bread)
+            {
+                use_to_satisfy_need(&mut commands, &tilemap_data, &mut reservations, pos, entity);
             }
         }
 
         if needs.sleep < NEEDS_MAX / 4 {
-            if let Some(pos) = TilemapData::find_from_center_chunk_size(pos, |index| {
-                matches!(
-                    tilemap_data.get(index),
-                    Some(TilePlaced {
-                        object: Some(ObjectId::Bed),
-                        ..
-                    })
-                ) && !q_tasks.iter().any(|t| t.pos == index)
-            }) {
-                commands.spawn(TaskBundle::new(
-                    Task::new(pos, TaskKind::UseToSatisfyNeed, Some(entity), &tilemap_data)
-                        .with_priority(1),
-                    TaskNeeds::Nothing,
-                ));
+            if let Some(pos) = find_unclaimed(&tilemap_data, &reservations, pos, ObjectId::Bed) {
+                use_to_satisfy_need(&mut commands, &tilemap_data, &mut reservations, pos, entity);
+            }
+        }
+    }
+}
+
+/// Nearest `object` that is not claimed by another dweller
+fn find_unclaimed(
+    tilemap_data: &TilemapData,
+    reservations: &Reservations,
+    pos: IVec2,
+    object: ObjectId,
+) -> Option<IVec2> {
+    TilemapData::find_from_center_chunk_size(pos, |index| {
+        tilemap_data.get(index).is_some_and(|tile| {
+            tile.object == Some(object) && reservations.available(index, tile) > 0
+        })
+    })
+}
+
+/// Send the dweller to use the object at `pos`, claiming one unit of it
+fn use_to_satisfy_need(
+    commands: &mut Commands,
+    tilemap_data: &TilemapData,
+    reservations: &mut Reservations,
+    pos: IVec2,
+    dweller: Entity,
+) {
+    let Some(object) = tilemap_data.get(pos).and_then(|tile| tile.object) else {
+        return;
+    };
+
+    let task = commands
+        .spawn(TaskBundle::new(
+            Task::new(pos, TaskKind::UseToSatisfyNeed, Some(dweller), tilemap_data)
+                .with_priority(1),
+            TaskNeeds::Nothing,
+        ))
+        .id();
+
+    reservations.reserve(
+        pos,
+        Reservation {
+            object,
+            count: 1,
+            task,
+            dweller: Some(dweller),
+        },
+    );
+}
+
+/// Dead dwellers leave a corpse and drop what they carried on the tiles around.
+/// Their tasks are released, the needs ones are removed since nobody else needs them.
+pub fn update_dweller_deaths(
+    mut commands: Commands,
+    mut tilemap_data: ResMut<TilemapData>,
+    mut dwellers_selected: Option<ResMut<DwellersSelected>>,
+    mut ev_notification: EventWriter<Notification>,
+    q_dwellers: Query<(Entity, &Dweller, &DwellerNeeds, &Transform)>,
+    mut q_tasks: Query<(Entity, &mut Task)>,
+) {
+    for (entity, dweller, needs, transform) in &q_dwellers {
+        if !needs.is_dead() {
+            continue;
+        }
+
+        let pos = IVec2::new(
+            (transform.translation.x / TILE_SIZE) as i32,
+            (transform.translation.y / TILE_SIZE) as i32,
+        );
+
+        // Drop on free tiles without tasks, so that the tasks stay valid
+        let tasks_positions = q_tasks
+            .iter()
+            .map(|(_, task)| task.pos)
+            .collect::<HashSet<_>>();
+
+        let dropped = std::iter::once((ObjectId::Corpse, 1, 0))
+            .chain(dweller.inventory.stacks_with_quality())
+            .chain(dweller.inventory.has_ration().then_some((RATION, 1, 0)))
+            .chain(dweller.tool.map(|(tool, quality)| (tool, 1, quality)))
+            .chain(dweller.armor.map(|(armor, quality)| (armor, 1, quality)));
+
+        for (object, count, quality) in dropped {
+            let Some(drop_pos) = TilemapData::find_from_center_chunk_size(pos, |pos| {
+                !tasks_positions.contains(&pos)
+                    && tilemap_data.get(pos).is_some_and(TilePlaced::is_floor_free)
+            }) else {
+                error!(
+                    "No free tile to drop {object:?} of dead dweller {}",
+                    dweller.name
+                );
+                continue;
+            };
+
+            if let Some(tile) = tilemap_data.get(drop_pos) {
+                tilemap_data.set(drop_pos, tile.add_with_quality(object, count, quality));
+            }
+        }
+
+        for (entity_task, mut task) in &mut q_tasks {
+            if task.dweller != Some(entity) {
+                continue;
+            }
+
+            if matches!(task.kind, TaskKind::UseToSatisfyNeed | TaskKind::Stockpile) {
+                commands.entity(entity_task).despawn_recursive();
+            } else {
+                task.dweller = None;
             }
         }
+
+        if let Some(dwellers_selected) = &mut dwellers_selected {
+            dwellers_selected.remove(entity);
+        }
+
+        commands.entity(entity).despawn_recursive();
+
+        ev_notification.send(Notification(format!("{} died", dweller.name)));
     }
 }
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use bevy::{
+    prelude::*,
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task as AsyncTask},
+};
+
+use crate::{dwellers::Dweller, tasks::Task, tilemap_data::TilemapData};
+
+/// Path to a task being computed on the `AsyncComputeTaskPool`, the task stays reserved for the dweller meanwhile
+#[derive(Component)]
+pub struct PendingPath {
+    task: Entity,
+    job: AsyncTask<Option<(Vec<IVec2>, i32)>>,
+}
+
+/// Wait for each path on the first poll, so that runs do not depend on thread timings.
+/// Used when running headless, where reproducibility matters more than frame times.
+#[derive(Resource)]
+pub struct WaitForPaths;
+
+impl PendingPath {
+    /// Queue a path search from the dweller to the closest reachable position of the task.
+    /// None if the task is known to be unreachable.
+    /// The snapshot is taken once and shared by all the requests of a system run.
+    pub fn request(
+        task_entity: Entity,
+        task: &Task,
+        dweller_pos: IVec2,
+        tilemap_data: &TilemapData,
+        snapshot: &mut Option<Arc<TilemapData>>,
+    ) -> Option<Self> {
+        let reachable_positions = task
+            .reachable_positions
+            .iter()
+            .copied()
+            .filter(|pos| tilemap_data.may_be_connected(*pos, dweller_pos))
+            .collect::<Vec<_>>();
+
+        if reachable_positions.is_empty() {
+            return None;
+        }
+
+        let snapshot = snapshot
+            .get_or_insert_with(|| Arc::new(tilemap_data.snapshot()))
+            .clone();
+
+        let job = AsyncComputeTaskPool::get().spawn(async move {
+            reachable_positions
+                .into_iter()
+                .filter_map(|pos| snapshot.find_path(pos, dweller_pos))
+                .min_by_key(|path| path.1)
+        });
+
+        Some(Self {
+            task: task_entity,
+            job,
+        })
+    }
+}
+
+pub fn receive_dwellers_paths(
+    mut commands: Commands,
+    mut q_dwellers: Query<(Entity, &mut Dweller, &mut PendingPath)>,
+    mut q_tasks: Query<&mut Task>,
+    wait_for_paths: Option<Res<WaitForPaths>>,
+) {
+    for (entity, mut dweller, mut pending_path) in &mut q_dwellers {
+        let path = if wait_for_paths.is_some() {
+            block_on(&mut pending_path.job)
+        } else {
+            let Some(path) = block_on(future::poll_once(&mut pending_path.job)) else {
+                continue;
+            };
+            path
+        };
+
+        commands.entity(entity).remove::<PendingPath>();
+
+        // The task may have been completed, cancelled or reassigned meanwhile
+        let Ok(mut task) = q_tasks.get_mut(pending_path.task) else {
+            continue;
+        };
+
+        if task.dweller != Some(entity) {
+            continue;
+        }
+
+        if let Some((path, _)) = path {
+            debug!("Dweller {} has a path to {:?}", dweller.name, task);
+            dweller.move_queue = path;
+        } else {
+            info!("Dweller {} gives up {:?}", dweller.name, task);
+            task.dweller = None;
+            task.reachable_pathfinding = false;
+        }
+    }
+}
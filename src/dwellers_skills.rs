@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    data::ObjectId,
+    tasks::{TaskKind, WORK_PER_TICK},
+    tiles::TilePlaced,
+};
+
+const LEVEL_MAX: u32 = 20;
+const STARTING_LEVEL_MAX: u32 = 6;
+const XP_PER_LEVEL: u32 = 200;
+const MIN_XP_GAIN: u32 = 50;
+
+#[derive(PartialEq, Eq, Clone, Copy, Reflect, Debug)]
+pub enum Skill {
+    Mining,
+    Building,
+    Farming,
+    Cooking,
+    Smithing,
+    Hunting,
+}
+
+impl Skill {
+    pub const ALL: [Skill; 6] = [
+        Skill::Mining,
+        Skill::Building,
+        Skill::Farming,
+        Skill::Cooking,
+        Skill::Smithing,
+        Skill::Hunting,
+    ];
+
+    /// Skill used to complete a task, Workstation skills depend on the workstation on the tile
+    #[must_use]
+    pub fn for_task(task_kind: TaskKind, tile: TilePlaced) -> Option<Self> {
+        match task_kind {
+            TaskKind::Dig | TaskKind::Smoothen => Some(Skill::Mining),
+            TaskKind::Build { .. } => Some(Skill::Building),
+            TaskKind::Harvest => Some(Skill::Farming),
+            TaskKind::Hunt => Some(Skill::Hunting),
+            TaskKind::Workstation { .. } => match tile.object {
+                Some(ObjectId::Furnace) => Some(Skill::Cooking),
+                Some(_) => Some(Skill::Smithing),
+                None => None,
+            },
+            TaskKind::Pickup
+            | TaskKind::Stockpile
+            | TaskKind::Walk
+            | TaskKind::UseToSatisfyNeed => None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Reflect, Default, Clone, Copy, Debug)]
+pub struct SkillProgress {
+    level: u32,
+    xp: u32,
+}
+
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component, Default)]
+pub struct DwellerSkills {
+    skills: [SkillProgress; Skill::ALL.len()],
+}
+
+impl DwellerSkills {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        let mut skills = Self::default();
+
+        for skill in &mut skills.skills {
+            skill.level = rng.random_range(0..=STARTING_LEVEL_MAX);
+        }
+
+        skills
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn level(&self, skill: Skill) -> u32 {
+        self.skills[skill.index()].level
+    }
+
+    pub fn gain_xp(&mut self, skill: Skill, work: u32) {
+        let progress = &mut self.skills[skill.index()];
+
+        if progress.level >= LEVEL_MAX {
+            return;
+        }
+
+        progress.xp += work.max(MIN_XP_GAIN);
+
+        // Each level needs more xp than the previous one
+        while progress.level < LEVEL_MAX && progress.xp >= XP_PER_LEVEL * (progress.level + 1) {
+            progress.xp -= XP_PER_LEVEL * (progress.level + 1);
+            progress.level += 1;
+        }
+    }
+
+    /// Work points applied per tick: half speed at level 0, normal speed at level 5
+    #[must_use]
+    pub fn work_per_tick(&self, skill: Option<Skill>) -> u32 {
+        let level = skill.map_or(5, |skill| self.level(skill));
+        (WORK_PER_TICK * (5 + level) / 10).max(1)
+    }
+
+    /// Quality of a Workstation output, from 0 to the max level
+    #[must_use]
+    pub fn output_quality(&self, skill: Option<Skill>) -> u8 {
+        skill.map_or(0, |skill| self.level(skill) as u8)
+    }
+}
@@ -0,0 +1,146 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+/// Fixed ticks in an in-game hour, 10 seconds
+pub const TICKS_PER_HOUR: u64 = 64 * 10;
+pub const HOURS_PER_DAY: u64 = 24;
+pub const DAYS_PER_SEASON: u64 = 10;
+const SEASONS: [Season; 4] = [
+    Season::Spring,
+    Season::Summer,
+    Season::Autumn,
+    Season::Winter,
+];
+
+/// The night lasts from dusk to dawn
+const DAWN_HOUR: u64 = 6;
+const DUSK_HOUR: u64 = 20;
+/// Hours the light takes to fade before dusk, and to come back after dawn
+const TWILIGHT_HOURS: u64 = 2;
+/// Darkness of the night, the alpha of the tint over the tilemap
+const MAX_DARKNESS: f32 = 0.6;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+/// In-game calendar, advancing with the simulation ticks
+#[derive(Resource, Reflect, Clone, Copy, Debug)]
+#[reflect(Resource)]
+pub struct GameTime {
+    ticks: u64,
+}
+
+impl Default for GameTime {
+    /// The first morning, once the light is back
+    fn default() -> Self {
+        Self::at_hour(DAWN_HOUR + TWILIGHT_HOURS)
+    }
+}
+
+impl GameTime {
+    /// The first day at `hour`
+    #[must_use]
+    pub fn at_hour(hour: u64) -> Self {
+        Self {
+            ticks: hour * TICKS_PER_HOUR,
+        }
+    }
+
+    /// The first day at dusk, when the night begins
+    #[must_use]
+    pub fn at_nightfall() -> Self {
+        Self::at_hour(DUSK_HOUR)
+    }
+
+    #[inline]
+    fn ticks_in_day(self) -> u64 {
+        self.ticks % (TICKS_PER_HOUR * HOURS_PER_DAY)
+    }
+
+    #[inline]
+    fn days(self) -> u64 {
+        self.ticks / (TICKS_PER_HOUR * HOURS_PER_DAY)
+    }
+
+    #[must_use]
+    pub fn hour(&self) -> u64 {
+        self.ticks_in_day() / TICKS_PER_HOUR
+    }
+
+    #[must_use]
+    pub fn minute(&self) -> u64 {
+        self.ticks_in_day() % TICKS_PER_HOUR * 60 / TICKS_PER_HOUR
+    }
+
+    /// Day of the season, from 1
+    #[must_use]
+    pub fn day(&self) -> u64 {
+        self.days() % DAYS_PER_SEASON + 1
+    }
+
+    #[must_use]
+    pub fn season(&self) -> Season {
+        SEASONS[(self.days() / DAYS_PER_SEASON % 4) as usize]
+    }
+
+    /// Year, from 1
+    #[must_use]
+    pub fn year(&self) -> u64 {
+        self.days() / (DAYS_PER_SEASON * 4) + 1
+    }
+
+    #[must_use]
+    pub fn is_night(&self) -> bool {
+        self.hour() >= DUSK_HOUR || self.hour() < DAWN_HOUR
+    }
+
+    /// First tick of the night
+    #[must_use]
+    pub fn is_nightfall(&self) -> bool {
+        self.ticks_in_day() == DUSK_HOUR * TICKS_PER_HOUR
+    }
+
+    /// From 0 by day to `MAX_DARKNESS` at night, fading during twilights
+    #[must_use]
+    pub fn darkness(&self) -> f32 {
+        let hours = self.ticks_in_day() as f32 / TICKS_PER_HOUR as f32;
+        let twilight = TWILIGHT_HOURS as f32;
+
+        let light = if self.is_night() {
+            0.0
+        } else if hours < (DAWN_HOUR + TWILIGHT_HOURS) as f32 {
+            (hours - DAWN_HOUR as f32) / twilight
+        } else if hours >= (DUSK_HOUR - TWILIGHT_HOURS) as f32 {
+            (DUSK_HOUR as f32 - hours) / twilight
+        } else {
+            1.0
+        };
+
+        (1.0 - light) * MAX_DARKNESS
+    }
+}
+
+impl fmt::Display for GameTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Year {}, {:?} day {}, {:02}:{:02}",
+            self.year(),
+            self.season(),
+            self.day(),
+            self.hour(),
+            self.minute()
+        )
+    }
+}
+
+/// One tick of in-game time per simulation tick, so it stops while paused
+pub fn advance_game_time(mut game_time: ResMut<GameTime>) {
+    game_time.ticks += 1;
+}
@@ -0,0 +1,121 @@
+use bevy::{prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+use crate::{
+    dwellers::{Dweller, SpawnDwellersOnChunk},
+    dwellers_pathfinding::WaitForPaths,
+    game_time::GameTime,
+    simulation::{SimulationPlugin, SimulationStats, WorldSeed},
+    stockpiles::StockpileZone,
+    terrain::LoadChunk,
+    tilemap_data::TilemapData,
+    SaveName,
+};
+
+/// Chunks loaded around the origin. They are never unloaded, so no save file is read or written
+const CHUNKS_RADIUS: i32 = 1;
+
+pub struct HeadlessArgs {
+    pub seed: u32,
+    pub ticks: u32,
+}
+
+impl HeadlessArgs {
+    /// `headless <seed> <ticks>`, exits with the usage if the arguments are invalid
+    #[must_use]
+    pub fn from_args() -> Self {
+        let args = std::env::args().collect::<Vec<_>>();
+
+        let seed = args.get(1).and_then(|seed| seed.parse().ok());
+        let ticks = args.get(2).and_then(|ticks| ticks.parse().ok());
+
+        if let (Some(seed), Some(ticks)) = (seed, ticks) {
+            Self { seed, ticks }
+        } else {
+            eprintln!("Usage: {} <seed> <ticks>", args[0]);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Simulation without window nor renderer, with an empty tilemap
+pub fn headless_app(seed: u32) -> App {
+    let mut app = App::new();
+
+    app.add_plugins((MinimalPlugins, StatesPlugin, SimulationPlugin))
+        // Each update advances time by exactly one fixed tick
+        .insert_resource(TimeUpdateStrategy::ManualDuration(
+            Time::<Fixed>::default().timestep(),
+        ))
+        .insert_resource(SaveName(format!("headless_{seed}")))
+        .insert_resource(WorldSeed(seed))
+        .insert_resource(WaitForPaths)
+        .init_resource::<TilemapData>()
+        .add_systems(FixedUpdate, clear_tilemap_updates);
+
+    app.finish();
+    app.cleanup();
+
+    app
+}
+
+/// Update the app until `ticks` fixed ticks have run since its start
+pub fn run_ticks(app: &mut App, ticks: u32) {
+    while app.world().resource::<SimulationStats>().ticks < ticks {
+        app.update();
+    }
+}
+
+/// Run the simulation for a fixed number of ticks, then print a summary
+pub fn run_headless(args: &HeadlessArgs) {
+    let mut app = headless_app(args.seed);
+    app.add_systems(Startup, spawn_headless_terrain);
+
+    run_ticks(&mut app, args.ticks);
+
+    print_summary(app.world_mut(), args);
+}
+
+fn spawn_headless_terrain(
+    mut ev_load_chunk: EventWriter<LoadChunk>,
+    mut ev_spawn_dwellers: EventWriter<SpawnDwellersOnChunk>,
+) {
+    for x in -CHUNKS_RADIUS..=CHUNKS_RADIUS {
+        for y in -CHUNKS_RADIUS..=CHUNKS_RADIUS {
+            ev_load_chunk.send(LoadChunk(IVec2::new(x, y)));
+        }
+    }
+
+    ev_spawn_dwellers.send(SpawnDwellersOnChunk(IVec2::ZERO));
+}
+
+/// Nothing renders the tilemap, drop its pending updates
+fn clear_tilemap_updates(mut tilemap_data: ResMut<TilemapData>) {
+    tilemap_data.tiles_to_update.clear();
+    tilemap_data.chunks_to_remove.clear();
+}
+
+fn print_summary(world: &mut World, args: &HeadlessArgs) {
+    let mut q_dwellers = world.query::<&Dweller>();
+    let mut q_zones = world.query::<&StockpileZone>();
+
+    let dwellers = q_dwellers.iter(world).count();
+
+    let tilemap_data = world.resource::<TilemapData>();
+    let stockpiled_objects = q_zones
+        .iter(world)
+        .filter(|zone| !zone.graveyard)
+        .flat_map(StockpileZone::positions)
+        .filter_map(|pos| tilemap_data.get(pos))
+        .filter(|tile| tile.object.is_some())
+        .map(|tile| u32::from(tile.count))
+        .sum::<u32>();
+
+    let stats = world.resource::<SimulationStats>();
+
+    println!("Seed: {}", args.seed);
+    println!("Ticks: {}", stats.ticks);
+    println!("Time: {}", world.resource::<GameTime>());
+    println!("Dwellers alive: {dwellers}");
+    println!("Tasks done: {}", stats.tasks_done);
+    println!("Objects in stockpiles: {stockpiled_objects}");
+}
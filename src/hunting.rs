@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+
+use crate::{
+    dwellers::Dweller,
+    mobs::Mob,
+    notifications::Notification,
+    tasks::{Task, TaskKind},
+    tilemap::TILE_SIZE,
+    tilemap_data::TilemapData,
+};
+
+/// Distance in tiles a prey can flee from where its hunt was ordered before the hunt is abandoned
+#[derive(Resource, Reflect, Clone, Copy, Debug)]
+#[reflect(Resource)]
+pub struct HuntDistance(pub i32);
+
+impl Default for HuntDistance {
+    fn default() -> Self {
+        Self(20)
+    }
+}
+
+/// Position of the prey when the hunt was ordered
+#[derive(Component, Reflect, Clone, Copy, Default, Debug)]
+#[reflect(Component)]
+pub struct HuntOrigin(pub IVec2);
+
+/// Follow the fleeing prey: Hunt tasks move with their mob, and hunters on their way
+/// pathfind again towards its new position. Hunts whose prey fled too far are abandoned.
+pub fn update_hunts(
+    mut commands: Commands,
+    tilemap_data: Res<TilemapData>,
+    hunt_distance: Res<HuntDistance>,
+    mut ev_notification: EventWriter<Notification>,
+    q_mobs: Query<(&Mob, &Transform)>,
+    mut q_dwellers: Query<&mut Dweller>,
+    mut q_tasks: Query<(Entity, &mut Task, &Parent, Option<&HuntOrigin>)>,
+) {
+    for (entity, mut task, parent, origin) in &mut q_tasks {
+        if task.kind != TaskKind::Hunt {
+            continue;
+        }
+
+        let Ok((mob, mob_transform)) = q_mobs.get(parent.get()) else {
+            continue;
+        };
+
+        let mob_pos = (mob_transform.translation / TILE_SIZE)
+            .truncate()
+            .as_ivec2();
+
+        let origin = origin.map_or_else(
+            || {
+                commands.entity(entity).insert(HuntOrigin(task.pos));
+                task.pos
+            },
+            |origin| origin.0,
+        );
+
+        if (mob_pos - origin).abs().max_element() > hunt_distance.0 {
+            commands.entity(entity).despawn_recursive();
+
+            let hunter = task
+                .dweller
+                .and_then(|dweller| q_dwellers.get_mut(dweller).ok())
+                .map_or_else(
+                    || "The hunters".to_string(),
+                    |mut dweller| {
+                        // Stop walking to the prey
+                        dweller.stop_after_step();
+                        dweller.name.clone()
+                    },
+                );
+            ev_notification.send(Notification(format!(
+                "{hunter} lost track of a fleeing {:?}",
+                mob.id
+            )));
+
+            continue;
+        }
+
+        if mob_pos == task.pos {
+            continue;
+        }
+
+        task.pos = mob_pos;
+        task.recompute_reachable_positions(&tilemap_data);
+
+        // Stop walking to the old position, update_dwellers finds a new path after this step
+        if let Some(mut dweller) = task.dweller.and_then(|d| q_dwellers.get_mut(d).ok()) {
+            if dweller.move_queue.len() > 1 {
+                debug!(
+                    "Dweller {} chases a {:?} to {mob_pos:?}",
+                    dweller.name, mob.id
+                );
+                dweller.stop_after_step();
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, sprite::Material2dPlugin, time::common_conditions::on_timer};
+use bevy_ecs_tilemap::TilemapPlugin;
+use rand::{distr::Alphanumeric, Rng};
+
+use crate::{
+    actions::*, camera::*, dwellers::*, game_time::*, mobs::*, objects::*, preview_sprites::*,
+    save_load::*, simulation::*, state::*, stockpiles::*, tasks::*, terrain::*, tilemap::*,
+    tiles::*, ui::*,
+};
+
+mod actions;
+mod camera;
+pub mod combat;
+pub mod data;
+pub mod dwellers;
+pub mod dwellers_inventory;
+pub mod dwellers_needs;
+pub mod dwellers_pathfinding;
+pub mod dwellers_skills;
+pub mod game_time;
+pub mod headless;
+pub mod hunting;
+pub mod mobs;
+pub mod mood;
+pub mod notifications;
+pub mod objects;
+mod preview_sprites;
+mod random_text;
+pub mod reservations;
+pub mod save_load;
+pub mod scenario;
+pub mod simulation;
+pub mod state;
+pub mod stockpiles;
+mod structures;
+pub mod tasks;
+pub mod terrain;
+pub mod tilemap;
+pub mod tilemap_data;
+mod tilemap_pathfinding;
+mod tilemap_regions;
+pub mod tiles;
+mod ui;
+mod utils;
+
+/// The whole game: simulation, tilemap rendering, camera, UI and save/load. Needs `DefaultPlugins`
+pub struct DwellersPlugin;
+
+impl Plugin for DwellersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            TilemapPlugin,
+            SimulationPlugin,
+            SaveLoadPlugin,
+            Material2dPlugin::<BackgroundMaterial>::default(),
+        ))
+        .init_resource::<CameraControl>()
+        .add_systems(
+            Startup,
+            (spawn_camera, spawn_new_terrain, spawn_ui, spawn_colony_ui),
+        )
+        .add_systems(
+            PreUpdate,
+            update_world_seed
+                .run_if(resource_changed::<SaveName>)
+                .before(seed_simulation_rng),
+        )
+        .add_systems(
+            Update,
+            (
+                init_font,
+                update_ui_buttons,
+                update_workstation_ui,
+                update_stockpile_zone_ui,
+                toggle_work_priorities_ui,
+                update_camera,
+                toggle_state,
+                (
+                    // Game UI / "reactive" systems
+                    keyboard_current_action,
+                    focus_any_dweller,
+                    terrain_draw_selection,
+                    update_dwellers_selected,
+                    spawn_dwellers_name,
+                    update_dwellers_equipment_sprites,
+                    update_task_needs_preview,
+                    update_task_build_preview,
+                    update_task_workstation_preview,
+                    update_task_progress_preview,
+                    spawn_stockpile_zones_sprite,
+                    update_colony_mood_ui,
+                    update_game_time_ui,
+                    update_darkness.run_if(resource_changed::<GameTime>),
+                    update_notifications_ui,
+                )
+                    .in_set(GameplaySet),
+            ),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                (update_dwellers_load_chunks).run_if(on_timer(Duration::from_secs(1))),
+                manage_chunks,
+                update_tilemap_from_data.after(manage_chunks),
+            )
+                .in_set(GameplaySet),
+        )
+        .add_observer(terrain_pointer_down)
+        .add_observer(terrain_pointer_up)
+        .add_observer(observe_open_workstation_ui)
+        .add_observer(observe_open_stockpile_zone_ui)
+        .insert_resource(SaveName({
+            rand::rng()
+                .sample_iter(&Alphanumeric)
+                .take(10)
+                .map(char::from)
+                .collect()
+        }))
+        .init_resource::<CurrentAction>()
+        .init_resource::<DwellersSelected>();
+    }
+}
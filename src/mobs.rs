@@ -1,42 +1,124 @@
 use bevy::prelude::*;
 use rand::prelude::*;
+use serde::Deserialize;
 
 use crate::{
     data::{MobId, ObjectId},
+    dwellers::Dweller,
+    game_time::GameTime,
+    simulation::SimulationRng,
     tilemap::TILE_SIZE,
     tilemap_data::TilemapData,
-    SpriteLoader, CHUNK_SIZE,
+    SpriteLoader, TilePlaced, CHUNK_SIZE,
 };
 
 const Z_INDEX: f32 = 11.0;
 
+/// Trees in a chunk for it to be a forest, where stalkers have their lair
+const FOREST_TREES: u32 = 150;
+/// Other dwellers this close keep a dweller from being stalked
+const LONE_RADIUS: i32 = 4;
+/// Walls this close in the 4 directions shelter a dweller from stalkers
+const SHELTER_RADIUS: i32 = 8;
+
 #[derive(Event)]
-pub struct SpawnMobsOnChunk(pub IVec2);
+pub struct SpawnMobsOnChunk(pub IVec2, pub MobSpawnTrigger);
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum MobSpawnTrigger {
+    /// The chunk was just generated, wildlife lives there
+    Generated,
+    /// Night falls on a loaded chunk, only night mobs come out
+    Nightfall,
+}
 
+#[derive(Deserialize)]
 pub struct MobData {
-    sprite_name: &'static str,
+    sprite_name: String,
     speed: f32,
     loot: ObjectId,
+    health: u32,
+    /// Damage of its attacks, 0 if it never fights
+    #[serde(default)]
+    damage: u32,
+    #[serde(default)]
+    profile: MobProfile,
+}
+
+#[derive(Reflect, PartialEq, Clone, Copy, Default, Deserialize, Debug)]
+pub enum MobBehaviour {
+    /// Wanders around
+    #[default]
+    Passive,
+    /// Runs away from the dwellers it perceives
+    Skittish,
+    /// Chases the dwellers entering its territory, around its spawn, and goes back there
+    Territorial,
+    /// Chases the dwellers it perceives
+    Aggressive,
+    /// Chases at night the dwellers it perceives alone outside walls, goes back to its lair
+    /// around its spawn at dawn and leaves
+    Stalker,
+}
+
+impl MobBehaviour {
+    /// Attacks any dweller coming close, not only the ones hunting it
+    #[must_use]
+    pub fn is_hostile(self) -> bool {
+        matches!(
+            self,
+            MobBehaviour::Territorial | MobBehaviour::Aggressive | MobBehaviour::Stalker
+        )
+    }
+}
+
+/// How a mob reacts to the dwellers around it
+#[derive(Reflect, Clone, Copy, Default, Deserialize, Debug)]
+pub struct MobProfile {
+    pub behaviour: MobBehaviour,
+    /// Distance in tiles at which dwellers are noticed, also the radius of a territory
+    #[serde(default)]
+    pub perception: i32,
 }
 
 impl MobData {
-    pub fn new(sprite_name: &'static str, speed: f32, loot: ObjectId) -> Self {
-        MobData {
-            sprite_name,
-            speed,
-            loot,
-        }
+    #[must_use]
+    pub fn sprite_path(&self) -> String {
+        format!("sprites/{}.png", self.sprite_name)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn health(&self) -> u32 {
+        self.health
     }
 }
 
 #[derive(Component, Reflect, Default)]
 #[reflect(Component)]
 pub struct Mob {
+    pub id: MobId,
     speed: f32,
     pub loot: ObjectId,
+    pub health: u32,
+    pub damage: u32,
+    pub profile: MobProfile,
+    /// Spawn position, the center of the territory
+    home: IVec2,
+    /// Where the path in `move_queue` leads, None for a single step
+    path_target: Option<IVec2>,
     move_queue: Vec<IVec2>, // next move is at the end
 }
 
+impl Mob {
+    /// Forget the planned path but finish the step in progress, so that the mob stops on a tile
+    fn stop_after_step(&mut self) {
+        let steps = self.move_queue.len();
+        self.move_queue.drain(..steps.saturating_sub(1));
+        self.path_target = None;
+    }
+}
+
 #[derive(Bundle)]
 pub struct MobBundle {
     mob: Mob,
@@ -45,15 +127,22 @@ pub struct MobBundle {
 }
 
 impl MobBundle {
+    #[must_use]
     pub fn new(id: MobId, index: IVec2) -> Self {
         MobBundle {
             mob: Mob {
+                id,
                 speed: id.data().speed,
                 loot: id.data().loot,
+                health: id.data().health,
+                damage: id.data().damage,
+                profile: id.data().profile,
+                home: index,
+                path_target: None,
                 move_queue: Vec::new(),
             },
             sprite: SpriteLoader {
-                texture_path: format!("sprites/{}.png", id.data().sprite_name),
+                texture_path: id.data().sprite_path(),
             },
             transform: Transform::from_xyz(
                 index.x as f32 * TILE_SIZE,
@@ -62,16 +151,50 @@ impl MobBundle {
             ),
         }
     }
+
+    /// Territory center or lair elsewhere than the spawn position
+    #[must_use]
+    pub fn with_home(mut self, home: IVec2) -> Self {
+        self.mob.home = home;
+        self
+    }
+}
+
+/// Night mobs may spawn on every loaded chunk at nightfall
+pub fn spawn_night_mobs(
+    game_time: Res<GameTime>,
+    tilemap_data: Res<TilemapData>,
+    mut ev_spawn: EventWriter<SpawnMobsOnChunk>,
+) {
+    if game_time.is_nightfall() {
+        debug!("Night falls");
+
+        // Sorted to spawn in the same order every run
+        let mut chunks = tilemap_data.chunks.keys().copied().collect::<Vec<_>>();
+        chunks.sort_by_key(|chunk_index| (chunk_index.x, chunk_index.y));
+
+        for chunk_index in chunks {
+            ev_spawn.send(SpawnMobsOnChunk(chunk_index, MobSpawnTrigger::Nightfall));
+        }
+    }
 }
 
+/// Wildlife on the chunks just generated, and a werewolf on forest chunks at night
 pub fn spawn_mobs(
     mut commands: Commands,
     tilemap_data: Res<TilemapData>,
+    game_time: Res<GameTime>,
     mut ev_spawn: EventReader<SpawnMobsOnChunk>,
+    mut rng: ResMut<SimulationRng>,
 ) {
-    let mut rng = rand::rng();
+    for SpawnMobsOnChunk(chunk_index, trigger) in ev_spawn.read() {
+        let werewolf = game_time.is_night()
+            && tilemap_data.objects_in_chunk(*chunk_index, ObjectId::Tree) >= FOREST_TREES;
+
+        if *trigger == MobSpawnTrigger::Nightfall && !werewolf {
+            continue;
+        }
 
-    for SpawnMobsOnChunk(chunk_index) in ev_spawn.read() {
         let Some(index) = TilemapData::find_from_center_chunk_size(
             TilemapData::local_index_to_global(
                 *chunk_index,
@@ -98,9 +221,18 @@ pub fn spawn_mobs(
             },
         ) else {
             error!("No valid spawn position found for mobs");
-            return;
+            continue;
         };
 
+        if werewolf {
+            debug!("Werewolf spawned at {index:?}");
+            commands.spawn(MobBundle::new(MobId::Werewolf, index));
+        }
+
+        if *trigger == MobSpawnTrigger::Nightfall {
+            continue;
+        }
+
         let nb_sheeps = rng.random_range(1..=7);
         let nb_boars = rng.random_range(1..=5);
 
@@ -114,9 +246,28 @@ pub fn spawn_mobs(
     }
 }
 
-pub fn update_mobs(mut q_mobs: Query<(&mut Mob, &Transform)>, tilemap_data: Res<TilemapData>) {
-    for (mut mob, transform) in &mut q_mobs {
-        if !mob.move_queue.is_empty() {
+/// Move mobs one tile at a time, following their behaviour
+pub fn update_mobs(
+    mut commands: Commands,
+    mut q_mobs: Query<(Entity, &mut Mob, &Transform)>,
+    q_dwellers: Query<&Transform, With<Dweller>>,
+    tilemap_data: Res<TilemapData>,
+    game_time: Res<GameTime>,
+    mut rng: ResMut<SimulationRng>,
+) {
+    let dwellers_pos = q_dwellers
+        .iter()
+        .map(|transform| {
+            IVec2::new(
+                (transform.translation.x / TILE_SIZE) as i32,
+                (transform.translation.y / TILE_SIZE) as i32,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for (entity, mut mob, transform) in &mut q_mobs {
+        // A single step (wandering, fleeing) is finished before deciding again
+        if !mob.move_queue.is_empty() && mob.path_target.is_none() {
             continue;
         }
 
@@ -125,13 +276,143 @@ pub fn update_mobs(mut q_mobs: Query<(&mut Mob, &Transform)>, tilemap_data: Res<
             (transform.translation.y / TILE_SIZE) as i32,
         );
 
-        // Wander around
-        let mut rng = rand::rng();
+        let perception = mob.profile.perception;
+        let distance = |a: IVec2, b: IVec2| (a - b).abs().max_element();
 
+        // Nearest dweller perceived, measured from `center`
+        let nearest_dweller = |center: IVec2| {
+            dwellers_pos
+                .iter()
+                .copied()
+                .filter(|pos| distance(center, *pos) <= perception)
+                .min_by_key(|pos| distance(index, *pos))
+        };
+
+        // Path to `target` from the step in progress, stopping next to `target`, next move at the end.
+        // The search may only stray `perception` tiles further than `target`
+        let path_to = |target: IVec2, start: IVec2| {
+            if !tilemap_data.may_be_connected(start, target) {
+                return None;
+            }
+
+            let radius = distance(start, target) + perception;
+
+            tilemap_data
+                .find_path_within(start, target, radius)
+                .map(|(path, _)| path.into_iter().skip(1).rev().skip(1).collect::<Vec<_>>())
+        };
+
+        // Its prey: dwellers alone, outside walls
+        let nearest_prey = || {
+            dwellers_pos
+                .iter()
+                .copied()
+                .filter(|pos| distance(index, *pos) <= perception)
+                .filter(|pos| {
+                    dwellers_pos
+                        .iter()
+                        .all(|other| other == pos || distance(*pos, *other) > LONE_RADIUS)
+                })
+                .filter(|pos| !tilemap_data.is_walled_in(*pos, SHELTER_RADIUS))
+                .min_by_key(|pos| distance(index, *pos))
+        };
+
+        // Back in its lair at dawn, it leaves until another night
+        if mob.profile.behaviour == MobBehaviour::Stalker
+            && !game_time.is_night()
+            && distance(index, mob.home) <= 1
+        {
+            debug!("Mob {:?} went back to its lair at {:?}", mob.id, mob.home);
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let target = match mob.profile.behaviour {
+            MobBehaviour::Passive | MobBehaviour::Skittish => None,
+
+            // Next to the dweller, the attack is handled by update_combat
+            MobBehaviour::Aggressive => {
+                nearest_dweller(index).filter(|dweller_pos| distance(index, *dweller_pos) > 1)
+            }
+
+            // Intruders first, then back to its territory
+            MobBehaviour::Territorial => match nearest_dweller(mob.home) {
+                Some(dweller_pos) => (distance(index, dweller_pos) > 1).then_some(dweller_pos),
+                None => (distance(index, mob.home) > perception).then_some(mob.home),
+            },
+
+            MobBehaviour::Stalker if game_time.is_night() => {
+                nearest_prey().filter(|dweller_pos| distance(index, *dweller_pos) > 1)
+            }
+
+            // Dawn
+            MobBehaviour::Stalker => Some(mob.home),
+        };
+
+        if let Some(target) = target {
+            // Keep following the path while its target stays there and nothing blocks it
+            if mob.path_target == Some(target)
+                && !mob.move_queue.is_empty()
+                && mob.move_queue.iter().all(|pos| {
+                    tilemap_data
+                        .get(*pos)
+                        .is_some_and(|tile| !tile.is_blocking())
+                })
+            {
+                continue;
+            }
+
+            let step_in_progress = mob.move_queue.last().copied();
+
+            if let Some(path) = path_to(target, step_in_progress.unwrap_or(index)) {
+                mob.move_queue = path;
+                mob.move_queue.extend(step_in_progress);
+                mob.path_target = Some(target);
+                continue;
+            }
+
+            // Its lair cannot be reached at dawn, it leaves from where it is
+            if mob.profile.behaviour == MobBehaviour::Stalker && !game_time.is_night() {
+                debug!("Mob {:?} cannot reach its lair, it leaves", mob.id);
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        }
+
+        mob.stop_after_step();
+
+        if !mob.move_queue.is_empty() {
+            continue;
+        }
+
+        let step = match mob.profile.behaviour {
+            MobBehaviour::Skittish => nearest_dweller(index).and_then(|dweller_pos| {
+                tilemap_data
+                    .non_blocking_neighbours_pos(index, true)
+                    .into_iter()
+                    .max_by_key(|pos| distance(*pos, dweller_pos))
+                    .filter(|pos| distance(*pos, dweller_pos) > distance(index, dweller_pos))
+            }),
+            _ => None,
+        };
+
+        if let Some(step) = step {
+            mob.move_queue.push(step);
+            continue;
+        }
+
+        // Fighting a dweller next to it, stay there
+        if mob.profile.behaviour.is_hostile()
+            && nearest_dweller(index).is_some_and(|pos| distance(index, pos) <= 1)
+        {
+            continue;
+        }
+
+        // Wander around
         if rng.random_bool(0.2) {
             let directions = tilemap_data.non_blocking_neighbours_pos(index, true);
 
-            if let Some(direction) = directions.choose(&mut rng) {
+            if let Some(direction) = directions.choose(&mut **rng) {
                 mob.move_queue.push(*direction);
             }
         }
@@ -140,9 +421,10 @@ pub fn update_mobs(mut q_mobs: Query<(&mut Mob, &Transform)>, tilemap_data: Res<
 
 pub fn update_mobs_movement(
     time: Res<Time>,
-    mut q_mobs: Query<(&mut Mob, &mut Transform, &mut Sprite)>,
+    tilemap_data: Res<TilemapData>,
+    mut q_mobs: Query<(&mut Mob, &mut Transform, Option<&mut Sprite>)>,
 ) {
-    for (mut mob, mut transform, mut sprite) in &mut q_mobs {
+    for (mut mob, mut transform, sprite) in &mut q_mobs {
         // Move to next position in queue
 
         if let Some(next_move) = mob.move_queue.last() {
@@ -153,16 +435,25 @@ pub fn update_mobs_movement(
 
             let direction = target - transform.translation.truncate();
 
-            if direction.length() < mob.speed * time.delta_secs() {
+            let speed = mob.speed
+                * tilemap_data
+                    .get(*next_move)
+                    .map_or(1.0, TilePlaced::speed_ratio)
+                * time.delta_secs();
+
+            if direction.length() < speed {
                 transform.translation.x = target.x;
                 transform.translation.y = target.y;
                 mob.move_queue.pop();
             } else {
                 let dir = direction.normalize();
-                transform.translation.x += dir.x * mob.speed * time.delta_secs();
-                transform.translation.y += dir.y * mob.speed * time.delta_secs();
+                transform.translation.x += dir.x * speed;
+                transform.translation.y += dir.y * speed;
 
-                sprite.flip_x = dir.x < 0.0;
+                // No sprite when running headless
+                if let Some(mut sprite) = sprite {
+                    sprite.flip_x = dir.x < 0.0;
+                }
             }
         }
     }
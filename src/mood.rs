@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+
+use crate::{data::ObjectId, dwellers::Dweller, tilemap::TILE_SIZE, tilemap_data::TilemapData};
+
+const MOOD_MAX: u32 = 100;
+
+/// Unburied corpses this close to a dweller hurt the colony mood
+const CORPSE_RADIUS: i32 = 10;
+
+/// Mood of the whole colony, from 0 to 100
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
+pub struct ColonyMood(pub u32);
+
+impl Default for ColonyMood {
+    fn default() -> Self {
+        Self(MOOD_MAX)
+    }
+}
+
+impl ColonyMood {
+    /// An unhappy colony works slower: half speed at mood 0, full speed at the max mood
+    #[must_use]
+    pub fn work_per_tick(&self, work: u32) -> u32 {
+        (work * (MOOD_MAX + self.0) / (MOOD_MAX * 2)).max(1)
+    }
+}
+
+/// Each unburied corpse near the dwellers lowers the mood, it slowly recovers once they are buried
+pub fn update_colony_mood(
+    mut mood: ResMut<ColonyMood>,
+    tilemap_data: Res<TilemapData>,
+    q_dwellers: Query<&Transform, With<Dweller>>,
+) {
+    let dwellers_pos = q_dwellers
+        .iter()
+        .map(|transform| {
+            IVec2::new(
+                (transform.translation.x / TILE_SIZE) as i32,
+                (transform.translation.y / TILE_SIZE) as i32,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Corpses are carriable, only the indexed tiles holding carriable objects are searched
+    let corpses = tilemap_data
+        .carriable_objects()
+        .filter(|pos| {
+            dwellers_pos
+                .iter()
+                .any(|dweller_pos| (*dweller_pos - *pos).abs().max_element() <= CORPSE_RADIUS)
+        })
+        .filter_map(|pos| tilemap_data.get(pos))
+        .filter(|tile| tile.object == Some(ObjectId::Corpse))
+        .map(|tile| u32::from(tile.count))
+        .sum::<u32>();
+
+    if corpses > 0 {
+        mood.0 = mood.0.saturating_sub(corpses);
+        debug!("{corpses} unburied corpses, colony mood is {}", mood.0);
+    } else {
+        mood.0 = (mood.0 + 1).min(MOOD_MAX);
+    }
+}
@@ -0,0 +1,12 @@
+use bevy::prelude::*;
+
+/// Message shown to the player about something that happened in the colony
+#[derive(Event, Clone, Debug)]
+pub struct Notification(pub String);
+
+/// Log notifications, the UI shows them when there is one
+pub fn log_notifications(mut ev_notification: EventReader<Notification>) {
+    for Notification(message) in ev_notification.read() {
+        info!("Notification: {message}");
+    }
+}
@@ -1,69 +1,89 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
 pub struct ObjectData {
-    filename: &'static str,
+    filename: String,
+    #[serde(default)]
     blocking: bool,
+    #[serde(default)]
     slot: ObjectSlot,
+    /// Extra movement cost to walk through this object
+    #[serde(default)]
+    movement_cost: u32,
+    /// Units that fit on a single tile
+    #[serde(default = "default_stack")]
+    stack: u8,
+    /// Added to the attacks of the dweller holding it as a tool
+    #[serde(default)]
+    damage: u32,
+    /// Removed from the damage taken by the dweller wearing it
+    #[serde(default)]
+    armor: u32,
+}
+
+fn default_stack() -> u8 {
+    1
 }
 
+#[derive(Deserialize, Default)]
 pub enum ObjectSlot {
     Uncarriable,
+    #[default]
     Object,
     Tool,
     Armor,
 }
 
 impl ObjectData {
-    const fn new(filename: &'static str, blocking: bool, slot: ObjectSlot) -> Self {
-        Self {
-            filename,
-            blocking,
-            slot,
-        }
-    }
-
-    pub const fn passable(filename: &'static str) -> Self {
-        Self::new(filename, false, ObjectSlot::Object)
-    }
-
-    pub const fn blocking(filename: &'static str) -> Self {
-        Self::new(filename, true, ObjectSlot::Object)
-    }
-
-    pub const fn passable_non_carriable(filename: &'static str) -> Self {
-        Self::new(filename, false, ObjectSlot::Uncarriable)
+    #[inline]
+    #[must_use]
+    pub fn is_carriable(&self) -> bool {
+        !matches!(self.slot, ObjectSlot::Uncarriable)
     }
 
-    pub const fn blocking_non_carriable(filename: &'static str) -> Self {
-        Self::new(filename, true, ObjectSlot::Uncarriable)
+    #[inline]
+    #[must_use]
+    pub fn is_blocking(&self) -> bool {
+        self.blocking
     }
 
-    pub const fn tool(filename: &'static str) -> Self {
-        Self::new(filename, false, ObjectSlot::Tool)
+    #[inline]
+    #[must_use]
+    pub fn movement_cost(&self) -> u32 {
+        self.movement_cost
     }
 
-    pub const fn armor(filename: &'static str) -> Self {
-        Self::new(filename, false, ObjectSlot::Armor)
+    #[inline]
+    #[must_use]
+    pub fn max_stack(&self) -> u8 {
+        self.stack
     }
 
     #[inline]
-    pub fn is_carriable(&self) -> bool {
-        !matches!(self.slot, ObjectSlot::Uncarriable)
+    #[must_use]
+    pub fn damage(&self) -> u32 {
+        self.damage
     }
 
     #[inline]
-    pub fn is_blocking(&self) -> bool {
-        self.blocking
+    #[must_use]
+    pub fn armor(&self) -> u32 {
+        self.armor
     }
 
     #[inline]
-    pub fn filename(&self) -> &'static str {
-        self.filename
+    #[must_use]
+    pub fn filename(&self) -> &str {
+        &self.filename
     }
 
     #[inline]
+    #[must_use]
     pub fn slot(&self) -> &ObjectSlot {
         &self.slot
     }
 
+    #[must_use]
     pub fn sprite_path(&self) -> String {
         format!("tiles/objects/{}.png", self.filename)
     }
@@ -1,6 +1,6 @@
 use bevy::{prelude::*, sprite::Anchor, utils::HashMap};
 
-use crate::{Dweller, Task, TaskKind, TaskNeeds, TILE_SIZE};
+use crate::{BillMode, Dweller, Task, TaskKind, TaskNeeds, WorkstationBills, TILE_SIZE};
 
 #[derive(Component)]
 pub struct DwellerObjectPreview;
@@ -20,14 +20,15 @@ pub fn update_dwellers_equipment_sprites(
     q_armor_previews: Query<&DwellerArmorPreview>,
 ) {
     for (entity, dweller, sprite, children) in &q_dwellers {
-        if let Some(object_preview) = children
+        for object_preview in children
             .iter()
-            .find(|child| q_object_previews.get(**child).is_ok())
+            .filter(|child| q_object_previews.get(**child).is_ok())
         {
             commands.entity(*object_preview).despawn();
         }
 
-        if let Some(object) = dweller.object {
+        // Carried stacks are piled up, the first one at the bottom and the ration on top
+        for (i, (object, _)) in dweller.inventory.totals().enumerate() {
             commands.entity(entity).with_child((
                 DwellerObjectPreview,
                 Sprite {
@@ -38,8 +39,8 @@ pub fn update_dwellers_equipment_sprites(
                 },
                 Transform::from_translation(Vec3::new(
                     if sprite.flip_x { TILE_SIZE / 2.0 } else { 0.0 },
-                    0.0,
-                    1.25,
+                    i as f32 * 3.0,
+                    1.25 + i as f32 * 0.01,
                 ))
                 .with_scale(Vec3::splat(0.5)),
             ));
@@ -52,7 +53,7 @@ pub fn update_dwellers_equipment_sprites(
             commands.entity(*tool_preview).despawn();
         }
 
-        if let Some(tool) = dweller.tool {
+        if let Some((tool, _)) = dweller.tool {
             commands.entity(entity).with_child((
                 DwellerToolPreview,
                 Sprite {
@@ -73,7 +74,7 @@ pub fn update_dwellers_equipment_sprites(
             commands.entity(*armor_preview).despawn();
         }
 
-        if let Some(armor) = dweller.armor {
+        if let Some((armor, _)) = dweller.armor {
             commands.entity(entity).with_child((
                 DwellerArmorPreview,
                 Sprite {
@@ -187,42 +188,110 @@ pub fn update_task_build_preview(
 #[derive(Component)]
 pub struct TaskWorkstationPreview;
 
+/// Total amount left in the workstation bills, "+" when some bills keep a stock
 pub fn update_task_workstation_preview(
     mut commands: Commands,
-    q_tasks: Query<(Entity, &Task, Option<&Children>), Changed<Task>>,
+    q_tasks: Query<(Entity, &WorkstationBills, Option<&Children>), Changed<WorkstationBills>>,
     q_workstation_previews: Query<(), With<TaskWorkstationPreview>>,
+    mut changes: Local<HashMap<Entity, (u32, bool)>>,
+) {
+    for (entity, bills, children) in &q_tasks {
+        let amount = bills
+            .0
+            .iter()
+            .filter(|bill| bill.mode == BillMode::Repeat)
+            .map(|bill| bill.amount)
+            .sum::<u32>();
+        let keeps_stock = bills.0.iter().any(|bill| bill.mode == BillMode::UntilStock);
+
+        if changes.get(&entity) == Some(&(amount, keeps_stock)) {
+            continue;
+        }
+
+        changes.insert(entity, (amount, keeps_stock));
+
+        if let Some(children) = children {
+            for child in children {
+                if q_workstation_previews.get(*child).is_ok() {
+                    commands.entity(*child).despawn();
+                }
+            }
+        }
+
+        commands.entity(entity).with_child((
+            TaskWorkstationPreview,
+            Text2d::new(format!("{amount}{}", if keeps_stock { "+" } else { "" })),
+            Anchor::TopLeft,
+            Transform::from_xyz(1., TILE_SIZE, 1.0).with_scale(Vec3::splat(0.25)),
+        ));
+    }
+
+    changes.retain(|entity, _| commands.get_entity(*entity).is_some());
+}
+
+#[derive(Component)]
+pub struct TaskProgressPreview;
+
+pub fn update_task_progress_preview(
+    mut commands: Commands,
+    q_tasks: Query<(Entity, &Task, Option<&Children>), Changed<Task>>,
+    q_progress_previews: Query<(), With<TaskProgressPreview>>,
     mut changes: Local<HashMap<Entity, u32>>,
 ) {
+    const BAR_HEIGHT: f32 = 2.0;
+
     for (entity, task, children) in &q_tasks {
-        match task.kind {
-            // Workstation amount preview
-            TaskKind::Workstation { amount } => {
-                if let Some(old_amount) = changes.get(&entity) {
-                    if *old_amount == amount {
-                        continue;
-                    }
-                }
+        let work = task.kind.work();
 
-                changes.insert(entity, amount);
+        if work == 0 {
+            continue;
+        }
 
-                if let Some(children) = children {
-                    for child in children {
-                        if q_workstation_previews.get(*child).is_ok() {
-                            commands.entity(*child).despawn();
-                        }
-                    }
-                }
+        if let Some(old_progress) = changes.get(&entity) {
+            if *old_progress == task.progress {
+                continue;
+            }
+        }
 
-                commands.entity(entity).with_child((
-                    TaskWorkstationPreview,
-                    Text2d::new(format!("{amount}")),
-                    Anchor::TopLeft,
-                    Transform::from_xyz(1., TILE_SIZE, 1.0).with_scale(Vec3::splat(0.25)),
-                ));
+        changes.insert(entity, task.progress);
+
+        if let Some(children) = children {
+            for child in children {
+                if q_progress_previews.get(*child).is_ok() {
+                    commands.entity(*child).despawn();
+                }
             }
+        }
 
-            _ => {}
+        if task.progress == 0 {
+            continue;
         }
+
+        let ratio = (task.progress as f32 / work as f32).min(1.0);
+
+        commands.entity(entity).with_children(|c| {
+            c.spawn((
+                TaskProgressPreview,
+                Sprite {
+                    color: Color::BLACK.with_alpha(0.5),
+                    anchor: Anchor::BottomLeft,
+                    custom_size: Some(Vec2::new(TILE_SIZE, BAR_HEIGHT)),
+                    ..default()
+                },
+                Transform::from_xyz(0.0, 0.0, 2.0),
+            ));
+
+            c.spawn((
+                TaskProgressPreview,
+                Sprite {
+                    color: bevy::color::palettes::css::LIME.into(),
+                    anchor: Anchor::BottomLeft,
+                    custom_size: Some(Vec2::new(TILE_SIZE * ratio, BAR_HEIGHT)),
+                    ..default()
+                },
+                Transform::from_xyz(0.0, 0.0, 2.1),
+            ));
+        });
     }
 
     changes.retain(|entity, _| commands.get_entity(*entity).is_some());
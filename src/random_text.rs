@@ -3,7 +3,7 @@ use std::{io::BufRead, sync::LazyLock};
 use bevy::utils::HashMap;
 use rand::{
     distr::{weighted::WeightedIndex, Distribution},
-    rngs::ThreadRng,
+    Rng,
 };
 
 pub static NAMES: LazyLock<ProbabilityTable> = LazyLock::new(|| {
@@ -71,7 +71,7 @@ fn generate_table(spaced_file: String, accuracy: usize) -> ProbabilityTable {
 }
 
 // Generate one word from a ProbabilityTable
-pub fn generate_word(table: &ProbabilityTable, rng: &mut ThreadRng) -> String {
+pub fn generate_word(table: &ProbabilityTable, rng: &mut impl Rng) -> String {
     let mut out = " ".repeat(table.accuracy);
     loop {
         let chars_list: Vec<_> = out.chars().collect();
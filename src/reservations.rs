@@ -0,0 +1,60 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{data::ObjectId, tasks::Task, tiles::TilePlaced};
+
+/// Units of the object lying on a tile, claimed by a task
+#[derive(Clone, Copy, Debug)]
+pub struct Reservation {
+    pub object: ObjectId,
+    pub count: u8,
+    pub task: Entity,
+    /// Dweller the claim is made for, it is released if the task loses this dweller
+    pub dweller: Option<Entity>,
+}
+
+/// Claims on objects lying on tiles, so that dwellers do not race for the same ones.
+/// Claims are released when their task is cancelled, completed or abandoned.
+#[derive(Resource, Default)]
+pub struct Reservations(HashMap<IVec2, Vec<Reservation>>);
+
+impl Reservations {
+    pub fn reserve(&mut self, pos: IVec2, reservation: Reservation) {
+        debug!("Reserved {reservation:?} at {pos:?}");
+        self.0.entry(pos).or_default().push(reservation);
+    }
+
+    /// Units of `object` at `pos` claimed by tasks other than `except`
+    #[must_use]
+    pub fn reserved(&self, pos: IVec2, object: ObjectId, except: Option<Entity>) -> u8 {
+        self.0.get(&pos).map_or(0, |claims| {
+            claims
+                .iter()
+                .filter(|claim| claim.object == object && Some(claim.task) != except)
+                .map(|claim| claim.count)
+                .fold(0, u8::saturating_add)
+        })
+    }
+
+    /// Units of the object on `tile` that no task claimed
+    #[must_use]
+    pub fn available(&self, pos: IVec2, tile: TilePlaced) -> u8 {
+        tile.object.map_or(0, |object| {
+            tile.count.saturating_sub(self.reserved(pos, object, None))
+        })
+    }
+}
+
+/// Release the claims of despawned tasks, and of tasks whose dweller gave up
+pub fn update_reservations(mut reservations: ResMut<Reservations>, q_tasks: Query<&Task>) {
+    reservations.0.retain(|_, claims| {
+        claims.retain(|claim| {
+            q_tasks.get(claim.task).is_ok_and(|task| {
+                claim
+                    .dweller
+                    .is_none_or(|dweller| task.dweller == Some(dweller))
+            })
+        });
+
+        !claims.is_empty()
+    });
+}
@@ -3,7 +3,14 @@ pub use save_load_assets::*;
 pub use save_load_systems::*;
 pub use save_load_ui::*;
 
-use crate::{dwellers_needs::DwellerNeeds, Dweller, Mob, Task, TaskNeeds};
+use crate::{
+    dwellers_needs::DwellerNeeds,
+    dwellers_skills::DwellerSkills,
+    game_time::GameTime,
+    hunting::{HuntDistance, HuntOrigin},
+    mood::ColonyMood,
+    Dweller, HaulPriority, Mob, StockpileZone, Task, TaskNeeds, WorkstationBills,
+};
 
 mod save_load_assets;
 mod save_load_systems;
@@ -26,9 +33,17 @@ impl Plugin for SaveLoadPlugin {
         .register_type::<SaveName>()
         .register_type::<Dweller>()
         .register_type::<DwellerNeeds>()
+        .register_type::<DwellerSkills>()
         .register_type::<Mob>()
         .register_type::<Task>()
         .register_type::<TaskNeeds>()
+        .register_type::<WorkstationBills>()
+        .register_type::<StockpileZone>()
+        .register_type::<HaulPriority>()
+        .register_type::<HuntDistance>()
+        .register_type::<HuntOrigin>()
+        .register_type::<ColonyMood>()
+        .register_type::<GameTime>()
         .register_type::<SpriteLoader>();
     }
 }
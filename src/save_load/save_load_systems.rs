@@ -5,8 +5,9 @@ use bevy::{
 };
 
 use crate::{
-    init_tilemap, tilemap_data::TilemapData, utils::write_to_file, ChunkObjectLayer,
-    ChunkTileLayer, Dweller, GameState, Mob, Task, UnloadChunk,
+    game_time::GameTime, hunting::HuntDistance, init_tilemap, mood::ColonyMood,
+    tilemap_data::TilemapData, utils::write_to_file, ChunkObjectLayer, ChunkTileLayer, Dweller,
+    GameState, HaulPriority, Mob, StockpileZone, Task, UnloadChunk,
 };
 
 pub const SAVE_DIR: &str = "saves";
@@ -15,6 +16,14 @@ pub const SAVE_DIR: &str = "saves";
 #[reflect(Resource)]
 pub struct SaveName(pub String);
 
+impl SaveName {
+    /// World seed, based on the save name
+    #[must_use]
+    pub fn seed(&self) -> u32 {
+        self.0.as_bytes().iter().map(|b| u32::from(*b)).sum()
+    }
+}
+
 #[derive(Resource)]
 pub struct SaveGame;
 
@@ -43,6 +52,7 @@ pub fn save_world(
     q_dwellers: Query<Entity, With<Dweller>>,
     q_tasks: Query<Entity, With<Task>>,
     q_mobs: Query<Entity, With<Mob>>,
+    q_zones: Query<Entity, With<StockpileZone>>,
     world: &World,
 ) {
     if save_game.is_some() {
@@ -60,6 +70,10 @@ pub fn save_world(
                 .deny_all_resources()
                 .allow_all_components()
                 .allow_resource::<SaveName>()
+                .allow_resource::<HaulPriority>()
+                .allow_resource::<HuntDistance>()
+                .allow_resource::<GameTime>()
+                .allow_resource::<ColonyMood>()
                 .deny_component::<CameraRenderGraph>()
                 .deny_component::<CameraMainTextureUsages>()
                 .deny_component::<Sprite>()
@@ -67,6 +81,7 @@ pub fn save_world(
                 .extract_entities(q_dwellers.iter())
                 .extract_entities(q_tasks.iter())
                 .extract_entities(q_mobs.iter())
+                .extract_entities(q_zones.iter())
                 .remove_empty_entities()
                 .build();
 
@@ -100,6 +115,7 @@ pub fn load_world(
     q_dwellers: Query<Entity, With<Dweller>>,
     q_tasks: Query<Entity, With<Task>>,
     q_mobs: Query<Entity, With<Mob>>,
+    q_zones: Query<Entity, With<StockpileZone>>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     if let Some(load_game) = load_game {
@@ -128,6 +144,10 @@ pub fn load_world(
                 commands.entity(mob).despawn_recursive();
             }
 
+            for zone in q_zones.iter() {
+                commands.entity(zone).despawn_recursive();
+            }
+
             // Spawn new scene
             scene_spawner.spawn_dynamic(
                 asset_server.load(format!("{SAVE_DIR}/{}/entities.ron", load_game.0.clone())),
@@ -0,0 +1,482 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use bevy::{
+    log::{
+        tracing_subscriber::{self, layer::SubscriberExt, Layer},
+        Level,
+    },
+    prelude::*,
+    utils::{
+        tracing::{self, Subscriber},
+        HashMap,
+    },
+};
+use serde::Deserialize;
+
+use crate::{
+    data::{MobId, ObjectId, TileId},
+    dwellers::Dweller,
+    dwellers_inventory::Inventory,
+    dwellers_needs::DwellerNeeds,
+    game_time::GameTime,
+    headless::{headless_app, run_ticks},
+    mobs::{Mob, MobBundle},
+    simulation::SimulationStats,
+    stockpiles::StockpileZone,
+    tasks::{Bill, Task, TaskBundle, TaskKind, TaskNeeds, WorkstationBills},
+    tilemap::TILE_SIZE,
+    tilemap_data::TilemapData,
+    tiles::TilePlaced,
+};
+
+/// Errors logged since the error counter was installed, "SHOULD NEVER HAPPEN" ones included
+static ERRORS_LOGGED: AtomicUsize = AtomicUsize::new(0);
+
+struct ErrorCounterLayer;
+
+impl<S: Subscriber> Layer<S> for ErrorCounterLayer {
+    fn on_event(
+        &self,
+        event: &tracing::Event<'_>,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        if *event.metadata().level() == Level::ERROR {
+            ERRORS_LOGGED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Print warnings and errors, and count errors so that scenarios logging any fail
+pub fn install_error_counter() {
+    let subscriber = tracing_subscriber::registry().with(ErrorCounterLayer).with(
+        tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_filter(tracing_subscriber::filter::LevelFilter::WARN),
+    );
+
+    if tracing::subscriber::set_global_default(subscriber).is_err() {
+        eprintln!("A tracing subscriber is already set, logged errors will not be counted");
+    }
+}
+
+/// A small map region with dwellers, mobs and tasks, and the expected outcomes after some ticks.
+/// The first map row is the top one, positions are `(x, y)` from the bottom left tile.
+#[derive(Deserialize)]
+pub struct Scenario {
+    pub description: String,
+    #[serde(default)]
+    pub seed: u32,
+    pub ticks: u32,
+    /// Starts at nightfall instead of the morning
+    #[serde(default)]
+    pub night: bool,
+    pub legend: HashMap<char, TilePlaced>,
+    pub map: Vec<String>,
+    #[serde(default)]
+    pub dwellers: Vec<ScenarioDweller>,
+    #[serde(default)]
+    pub mobs: Vec<ScenarioMob>,
+    #[serde(default)]
+    pub tasks: Vec<ScenarioTask>,
+    #[serde(default)]
+    pub zones: Vec<ScenarioZone>,
+    pub expect: Vec<Expectation>,
+}
+
+#[derive(Deserialize)]
+pub struct ScenarioDweller {
+    pub pos: (i32, i32),
+    /// Carried stacks, `(object, count)`
+    #[serde(default)]
+    pub inventory: Vec<(ObjectId, u8)>,
+    #[serde(default)]
+    pub tool: Option<ObjectId>,
+    #[serde(default)]
+    pub armor: Option<ObjectId>,
+    /// (health, food, sleep), full by default
+    #[serde(default)]
+    pub needs: Option<(u32, u32, u32)>,
+}
+
+#[derive(Deserialize)]
+pub struct ScenarioMob {
+    pub id: MobId,
+    pub pos: (i32, i32),
+    /// Center of its territory or its lair, `pos` by default
+    #[serde(default)]
+    pub home: Option<(i32, i32)>,
+}
+
+#[derive(Deserialize)]
+pub struct ScenarioTask {
+    pub pos: (i32, i32),
+    pub kind: TaskKind,
+    #[serde(default)]
+    pub needs: TaskNeeds,
+    #[serde(default)]
+    pub priority: i32,
+    /// Bills of Workstation tasks
+    #[serde(default)]
+    pub bills: Vec<Bill>,
+}
+
+#[derive(Deserialize)]
+pub struct ScenarioZone {
+    pub min: (i32, i32),
+    pub max: (i32, i32),
+    #[serde(default)]
+    pub priority: i32,
+    /// Every carriable object by default
+    #[serde(default)]
+    pub allowed: Option<Vec<ObjectId>>,
+    /// Corpses are buried there, nothing is stored
+    #[serde(default)]
+    pub graveyard: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub enum Expectation {
+    /// The tile at `pos` has this id
+    Tile { pos: (i32, i32), id: TileId },
+    /// The object on the tile at `pos`, None for no object
+    Object {
+        pos: (i32, i32),
+        object: Option<ObjectId>,
+    },
+    /// A stack of `count` units of the object lies on `pos`
+    Stack {
+        pos: (i32, i32),
+        object: ObjectId,
+        count: u8,
+    },
+    /// The units stacked on `pos` have this quality
+    Quality { pos: (i32, i32), quality: u8 },
+    /// The object lies on `pos` or one of the 8 tiles around it
+    ObjectNear { pos: (i32, i32), object: ObjectId },
+    /// No task remains at `pos`
+    NoTask { pos: (i32, i32) },
+    /// Number of tasks of this kind
+    Tasks { kind: TaskKind, count: usize },
+    /// Number of objects on the ground or carried by dwellers
+    Stock { object: ObjectId, count: u32 },
+    /// Number of mobs of this kind
+    Mobs { id: MobId, count: usize },
+    /// Number of dwellers alive
+    DwellersAlive(usize),
+    /// Minimum number of tasks done
+    TasksDone(u32),
+}
+
+#[inline]
+fn to_pos((x, y): (i32, i32)) -> IVec2 {
+    IVec2::new(x, y)
+}
+
+/// Scenario files of a directory, sorted by name
+///
+/// # Errors
+/// If the directory cannot be read
+pub fn scenario_paths(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut paths = std::fs::read_dir(dir)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    Ok(paths)
+}
+
+impl Scenario {
+    /// # Errors
+    /// If the file cannot be read or parsed
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        ron::from_str(&content).map_err(|e| e.to_string())
+    }
+
+    /// Run the scenario in a headless app
+    ///
+    /// # Errors
+    /// The unmet expectations, and the errors logged during the run
+    pub fn run(&self) -> Result<(), Vec<String>> {
+        let mut app = headless_app(self.seed);
+        self.spawn(app.world_mut())?;
+
+        let errors_before = ERRORS_LOGGED.load(Ordering::Relaxed);
+        run_ticks(&mut app, self.ticks);
+        let errors = ERRORS_LOGGED.load(Ordering::Relaxed) - errors_before;
+
+        let mut failures = self
+            .expect
+            .iter()
+            .filter_map(|expectation| expectation.check(app.world_mut()).err())
+            .collect::<Vec<_>>();
+
+        if errors > 0 {
+            failures.push(format!("{errors} errors logged"));
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures)
+        }
+    }
+
+    fn spawn(&self, world: &mut World) -> Result<(), Vec<String>> {
+        if self.night {
+            world.insert_resource(GameTime::at_nightfall());
+        }
+
+        let mut tilemap_data = world.resource_mut::<TilemapData>();
+
+        for (y, row) in self.map.iter().rev().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let Some(tile) = self.legend.get(&c) else {
+                    return Err(vec![format!("Character '{c}' is not in the legend")]);
+                };
+
+                tilemap_data.set(IVec2::new(x as i32, y as i32), *tile);
+            }
+        }
+
+        for (i, dweller) in self.dwellers.iter().enumerate() {
+            let needs = dweller.needs.map_or_else(DwellerNeeds::default, |needs| {
+                DwellerNeeds::new(needs.0, needs.1, needs.2)
+            });
+
+            world.spawn((
+                Dweller {
+                    name: format!("Dweller{i}"),
+                    inventory: Inventory::new(dweller.inventory.clone()),
+                    tool: dweller.tool.map(|tool| (tool, 0)),
+                    armor: dweller.armor.map(|armor| (armor, 0)),
+                    ..default()
+                },
+                needs,
+                Transform::from_translation(
+                    (to_pos(dweller.pos).as_vec2() * TILE_SIZE).extend(0.0),
+                ),
+            ));
+        }
+
+        let mobs = self
+            .mobs
+            .iter()
+            .map(|mob| {
+                (
+                    to_pos(mob.pos),
+                    world
+                        .spawn(
+                            MobBundle::new(mob.id, to_pos(mob.pos))
+                                .with_home(to_pos(mob.home.unwrap_or(mob.pos))),
+                        )
+                        .id(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for (i, scenario_zone) in self.zones.iter().enumerate() {
+            let (min, max) = (to_pos(scenario_zone.min), to_pos(scenario_zone.max));
+            let mut zone = if scenario_zone.graveyard {
+                StockpileZone::new_graveyard(format!("Graveyard {}", i + 1), min, max)
+            } else {
+                StockpileZone::new(format!("Stockpile {}", i + 1), min, max)
+            };
+            zone.priority = scenario_zone.priority;
+            if let Some(allowed) = &scenario_zone.allowed {
+                zone.allowed.clone_from(allowed);
+            }
+
+            world.spawn(zone);
+        }
+
+        for scenario_task in &self.tasks {
+            let pos = to_pos(scenario_task.pos);
+            let task = Task::new(
+                pos,
+                scenario_task.kind,
+                None,
+                world.resource::<TilemapData>(),
+            )
+            .with_priority(scenario_task.priority);
+
+            // Hunt tasks follow their mob
+            if matches!(scenario_task.kind, TaskKind::Hunt) {
+                let Some((_, mob)) = mobs.iter().find(|(mob_pos, _)| *mob_pos == pos) else {
+                    return Err(vec![format!("No mob to hunt at {pos}")]);
+                };
+
+                let task = world
+                    .spawn(TaskBundle::new_as_child(task, scenario_task.needs.clone()))
+                    .id();
+                world.entity_mut(*mob).add_child(task);
+            } else if matches!(scenario_task.kind, TaskKind::Workstation { .. }) {
+                world.spawn((
+                    TaskBundle::new(task, TaskNeeds::Impossible),
+                    WorkstationBills(scenario_task.bills.clone()),
+                ));
+            } else {
+                world.spawn(TaskBundle::new(task, scenario_task.needs.clone()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Expectation {
+    fn check(&self, world: &mut World) -> Result<(), String> {
+        let tilemap_data = world.resource::<TilemapData>();
+
+        match self {
+            Expectation::Tile { pos, id } => {
+                let tile = tilemap_data.get(to_pos(*pos));
+
+                if tile.is_some_and(|tile| tile.id == *id) {
+                    return Ok(());
+                }
+
+                Err(format!("Expected tile {id:?} at {pos:?}, found {tile:?}"))
+            }
+
+            Expectation::Object { pos, object } => {
+                let tile = tilemap_data.get(to_pos(*pos));
+
+                if tile.is_some_and(|tile| tile.object == *object) {
+                    return Ok(());
+                }
+
+                Err(format!(
+                    "Expected object {object:?} at {pos:?}, found {tile:?}"
+                ))
+            }
+
+            Expectation::Stack { pos, object, count } => {
+                let tile = tilemap_data.get(to_pos(*pos));
+
+                if tile.is_some_and(|tile| tile.object == Some(*object) && tile.count == *count) {
+                    return Ok(());
+                }
+
+                Err(format!(
+                    "Expected {count} {object:?} at {pos:?}, found {tile:?}"
+                ))
+            }
+
+            Expectation::Quality { pos, quality } => {
+                let tile = tilemap_data.get(to_pos(*pos));
+
+                if tile.is_some_and(|tile| tile.object.is_some() && tile.quality == *quality) {
+                    return Ok(());
+                }
+
+                Err(format!(
+                    "Expected quality {quality} at {pos:?}, found {tile:?}"
+                ))
+            }
+
+            Expectation::ObjectNear { pos, object } => {
+                let found = TilemapData::find_from_center(to_pos(*pos), 1, |pos| {
+                    tilemap_data
+                        .get(pos)
+                        .is_some_and(|tile| tile.object == Some(*object))
+                });
+
+                if found.is_some() {
+                    return Ok(());
+                }
+
+                Err(format!("Expected object {object:?} near {pos:?}"))
+            }
+
+            Expectation::NoTask { pos } => {
+                let mut q_tasks = world.query::<&Task>();
+
+                match q_tasks.iter(world).find(|task| task.pos == to_pos(*pos)) {
+                    Some(task) => Err(format!("Expected no task at {pos:?}, found {task:?}")),
+                    None => Ok(()),
+                }
+            }
+
+            Expectation::Tasks { kind, count } => {
+                let mut q_tasks = world.query::<&Task>();
+                let found = q_tasks
+                    .iter(world)
+                    .filter(|task| task.kind == *kind)
+                    .count();
+
+                if found == *count {
+                    return Ok(());
+                }
+
+                Err(format!("Expected {count} {kind:?} tasks, found {found}"))
+            }
+
+            Expectation::Stock { object, count } => {
+                let mut q_dwellers = world.query::<&Dweller>();
+                let carried = q_dwellers
+                    .iter(world)
+                    .flat_map(|dweller| dweller.inventory.totals())
+                    .filter(|(o, _)| o == object)
+                    .map(|(_, count)| u32::from(count))
+                    .sum::<u32>();
+
+                let tilemap_data = world.resource::<TilemapData>();
+                let on_ground = tilemap_data
+                    .chunks
+                    .values()
+                    .flat_map(|chunk| chunk.iter().flatten())
+                    .filter(|tile| tile.object == Some(*object))
+                    .map(|tile| u32::from(tile.count))
+                    .sum::<u32>();
+
+                let stock = carried + on_ground;
+                if stock == *count {
+                    return Ok(());
+                }
+
+                Err(format!(
+                    "Expected {count} {object:?} in stock, found {stock}"
+                ))
+            }
+
+            Expectation::Mobs { id, count } => {
+                let mut q_mobs = world.query::<&Mob>();
+                let found = q_mobs.iter(world).filter(|mob| mob.id == *id).count();
+
+                if found == *count {
+                    return Ok(());
+                }
+
+                Err(format!("Expected {count} {id:?} mobs, found {found}"))
+            }
+
+            Expectation::DwellersAlive(count) => {
+                let mut q_dwellers = world.query::<&Dweller>();
+                let alive = q_dwellers.iter(world).count();
+
+                if alive == *count {
+                    return Ok(());
+                }
+
+                Err(format!("Expected {count} dwellers alive, found {alive}"))
+            }
+
+            Expectation::TasksDone(count) => {
+                let done = world.resource::<SimulationStats>().tasks_done;
+
+                if done >= *count {
+                    return Ok(());
+                }
+
+                Err(format!(
+                    "Expected at least {count} tasks done, found {done}"
+                ))
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::{sync::LazyLock, time::Duration};
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    combat::*, data::DEFINITIONS, dwellers::*, dwellers_needs::*, dwellers_pathfinding::*,
+    game_time::*, hunting::*, mobs::*, mood::*, notifications::*, reservations::*, state::*,
+    stockpiles::*, tasks::*, terrain::*, SaveName,
+};
+
+/// Colony simulation: terrain, tasks, dwellers and mobs, without rendering, UI or input
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        LazyLock::force(&DEFINITIONS);
+
+        app.add_event::<LoadChunk>()
+            .add_event::<UnloadChunk>()
+            .add_event::<TaskCompletionEvent>()
+            .add_event::<SpawnDwellersOnChunk>()
+            .add_event::<SpawnMobsOnChunk>()
+            .add_event::<Notification>()
+            .configure_sets(Update, GameplaySet.run_if(in_state(GameState::Running)))
+            .configure_sets(
+                FixedUpdate,
+                GameplaySet.run_if(in_state(GameState::Running)),
+            )
+            .add_systems(
+                PreUpdate,
+                seed_simulation_rng.run_if(resource_changed::<WorldSeed>),
+            )
+            .add_systems(
+                Update,
+                (
+                    load_chunks,
+                    (spawn_dwellers, spawn_mobs).chain().after(load_chunks),
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    // Game logic
+                    // Systems drawing from SimulationRng are ordered to stay reproducible
+                    (update_dwellers, update_mobs, assign_tasks_to_dwellers)
+                        .chain()
+                        .after(update_pathfinding_caches)
+                        .run_if(on_timer(Duration::from_millis(200))),
+                    update_hunts
+                        .before(update_dwellers)
+                        .run_if(on_timer(Duration::from_secs(1))),
+                    (update_dweller_needs).run_if(on_timer(Duration::from_millis(600))),
+                    update_combat
+                        .after(event_task_completion)
+                        .run_if(on_timer(Duration::from_secs(1))),
+                    update_dweller_deaths
+                        .after(update_dweller_needs)
+                        .after(update_combat)
+                        .before(update_reservations),
+                    (update_graveyards, update_colony_mood)
+                        .after(event_task_completion)
+                        .run_if(on_timer(Duration::from_secs(1))),
+                    log_notifications,
+                    (update_terrain)
+                        .after(assign_tasks_to_dwellers)
+                        .run_if(on_timer(Duration::from_millis(800))),
+                    receive_dwellers_paths,
+                    update_dwellers_movement,
+                    update_mobs_movement,
+                    update_unreachable_tasks,
+                    update_unreachable_pathfinding_tasks.run_if(on_timer(Duration::from_secs(5))),
+                    update_pickups.run_if(on_timer(Duration::from_secs(1))),
+                    event_task_completion,
+                    update_hauling
+                        .after(event_task_completion)
+                        .after(update_pickups)
+                        .before(update_pathfinding_caches)
+                        .run_if(on_timer(Duration::from_secs(1))),
+                    (update_objects_stock, update_workstations_bills)
+                        .chain()
+                        .after(event_task_completion)
+                        .run_if(on_timer(Duration::from_secs(1))),
+                    update_pathfinding_caches.after(event_task_completion),
+                    // After the systems claiming objects, so that their new tasks exist
+                    update_reservations
+                        .after(update_dweller_needs)
+                        .after(update_pickups)
+                        .after(update_hauling),
+                    (
+                        count_simulation_ticks,
+                        advance_game_time,
+                        spawn_night_mobs.after(advance_game_time),
+                    ),
+                )
+                    .in_set(GameplaySet),
+            )
+            .init_state::<GameState>()
+            .init_resource::<WorldSeed>()
+            .init_resource::<SimulationRng>()
+            .init_resource::<SimulationStats>()
+            .init_resource::<TaskIds>()
+            .init_resource::<ObjectsStock>()
+            .init_resource::<Reservations>()
+            .init_resource::<ColonyMood>()
+            .init_resource::<HaulPriority>()
+            .init_resource::<HuntDistance>()
+            .init_resource::<GameTime>();
+    }
+}
+
+/// Seed of the world generation and of the simulation randomness
+#[derive(Resource, Default, Clone, Copy)]
+pub struct WorldSeed(pub u32);
+
+/// Source of all gameplay randomness, seeded from the world seed so that runs can be reproduced
+#[derive(Resource, Deref, DerefMut)]
+pub struct SimulationRng(StdRng);
+
+impl SimulationRng {
+    #[must_use]
+    pub fn from_seed(seed: u32) -> Self {
+        Self(StdRng::seed_from_u64(u64::from(seed)))
+    }
+}
+
+impl Default for SimulationRng {
+    fn default() -> Self {
+        Self::from_seed(0)
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct SimulationStats {
+    pub ticks: u32,
+    pub tasks_done: u32,
+}
+
+/// The world seed of a game comes from its save name
+pub fn update_world_seed(save_name: Res<SaveName>, mut world_seed: ResMut<WorldSeed>) {
+    world_seed.0 = save_name.seed();
+}
+
+pub fn seed_simulation_rng(world_seed: Res<WorldSeed>, mut rng: ResMut<SimulationRng>) {
+    *rng = SimulationRng::from_seed(world_seed.0);
+}
+
+pub fn count_simulation_ticks(mut stats: ResMut<SimulationStats>) {
+    stats.ticks += 1;
+}
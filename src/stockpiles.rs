@@ -0,0 +1,438 @@
+use bevy::{
+    prelude::*,
+    sprite::Anchor,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    data::{ObjectId, DEFINITIONS},
+    dwellers::{Dweller, WorkCategory},
+    dwellers_inventory::Inventory,
+    reservations::Reservations,
+    tasks::{BuildResult, Task, TaskBundle, TaskKind, TaskNeeds},
+    tilemap::TILE_SIZE,
+    tilemap_data::TilemapData,
+    tiles::TilePlaced,
+};
+
+const Z_INDEX: f32 = 1.0;
+
+/// Area where dwellers store the objects they carry.
+/// Objects are hauled from lower to higher priority zones, and out of zones that no longer allow them.
+/// Graveyards are not hauled to, corpses are buried there instead.
+#[derive(Component, Reflect, PartialEq, Clone, Default, Debug)]
+#[reflect(Component)]
+pub struct StockpileZone {
+    pub name: String,
+    pub min: IVec2,
+    pub max: IVec2,
+    pub allowed: Vec<ObjectId>,
+    pub priority: i32,
+    pub graveyard: bool,
+}
+
+impl StockpileZone {
+    /// Allows every haulable object but corpses
+    #[must_use]
+    pub fn new(name: String, min: IVec2, max: IVec2) -> Self {
+        Self {
+            name,
+            min,
+            max,
+            allowed: ObjectId::all()
+                .filter(|object| is_haulable(*object) && *object != ObjectId::Corpse)
+                .collect(),
+            priority: 0,
+            graveyard: false,
+        }
+    }
+
+    #[must_use]
+    pub fn new_graveyard(name: String, min: IVec2, max: IVec2) -> Self {
+        Self {
+            name,
+            min,
+            max,
+            allowed: Vec::new(),
+            priority: 0,
+            graveyard: true,
+        }
+    }
+
+    #[must_use]
+    pub fn contains(&self, pos: IVec2) -> bool {
+        pos.cmpge(self.min).all() && pos.cmple(self.max).all()
+    }
+
+    #[must_use]
+    pub fn accepts(&self, object: ObjectId) -> bool {
+        self.allowed.contains(&object)
+    }
+
+    pub fn toggle(&mut self, object: ObjectId) {
+        if let Some(i) = self.allowed.iter().position(|o| *o == object) {
+            self.allowed.remove(i);
+        } else {
+            self.allowed.push(object);
+        }
+    }
+
+    /// Tile positions, from the top left like the selection
+    pub fn positions(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (self.min.y..=self.max.y)
+            .rev()
+            .flat_map(|y| (self.min.x..=self.max.x).map(move |x| IVec2::new(x, y)))
+    }
+}
+
+/// Task priority of hauling, low by default so that hauling does not starve real work
+#[derive(Resource, Reflect, Clone, Copy, Debug)]
+#[reflect(Resource)]
+pub struct HaulPriority(pub i32);
+
+impl Default for HaulPriority {
+    fn default() -> Self {
+        Self(-1)
+    }
+}
+
+/// Loose objects are stored in zones, built furniture stays where it is
+#[must_use]
+pub fn is_haulable(object: ObjectId) -> bool {
+    object.data().is_carriable()
+        && !DEFINITIONS
+            .build_recipes
+            .iter()
+            .any(|(result, _)| *result == BuildResult::Object(object))
+}
+
+/// Send idle dwellers to store what they carry, or drop it if there is no room.
+/// Haul loose objects and objects in worse zones to the zones accepting them.
+pub fn update_hauling(
+    mut commands: Commands,
+    mut tilemap_data: ResMut<TilemapData>,
+    haul_priority: Res<HaulPriority>,
+    reservations: Res<Reservations>,
+    q_zones: Query<&StockpileZone>,
+    mut q_dwellers: Query<(Entity, &mut Dweller, &Transform)>,
+    q_tasks: Query<(Entity, &Task, &TaskNeeds)>,
+) {
+    // Highest priority first
+    let mut zones = q_zones
+        .iter()
+        .filter(|zone| !zone.graveyard)
+        .collect::<Vec<_>>();
+    zones.sort_by_key(|zone| -zone.priority);
+
+    // Stockpile tasks only live while their dweller carries the object there
+    for (entity, task, task_needs) in &q_tasks {
+        if task.kind != TaskKind::Stockpile {
+            continue;
+        }
+
+        let still_accepted = matches!(task_needs, TaskNeeds::Objects(objects)
+            if objects.iter().all(|object| zones
+                .iter()
+                .any(|zone| zone.contains(task.pos) && zone.accepts(*object))));
+
+        // The tile still has room for all the units brought
+        let still_free = matches!(task_needs, TaskNeeds::Objects(objects)
+            if objects.first().is_some_and(|object| tilemap_data
+                .get(task.pos)
+                .is_some_and(|tile| usize::from(tile.room_for(*object)) >= objects.len())));
+
+        if task.dweller.is_none() || !still_accepted || !still_free {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    let mut tasks_positions = q_tasks
+        .iter()
+        .filter(|(_, task, _)| task.kind != TaskKind::Stockpile || task.dweller.is_some())
+        .map(|(_, task, _)| task.pos)
+        .collect::<HashSet<_>>();
+
+    // Empty tiles, or stacks with room left
+    let mut free_positions = zones
+        .iter()
+        .map(|zone| {
+            zone.positions()
+                .filter(|pos| {
+                    !tasks_positions.contains(pos)
+                        && tilemap_data
+                            .get(*pos)
+                            .is_some_and(|tile| TaskKind::Stockpile.is_valid_on_tile(tile))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let assigned_dwellers = q_tasks
+        .iter()
+        .filter_map(|(_, task, _)| task.dweller)
+        .collect::<HashSet<_>>();
+
+    for (entity_dweller, mut dweller, transform) in &mut q_dwellers {
+        // Stacks are stored one at a time, the last one first as its units are taken first
+        let Some((object, carried, quality)) = dweller.inventory.stacks_with_quality().last()
+        else {
+            continue;
+        };
+
+        // Dwellers not hauling keep what they carry until a task needs it
+        if assigned_dwellers.contains(&entity_dweller)
+            || dweller.work_priorities.get(WorkCategory::Hauling).is_none()
+        {
+            continue;
+        }
+
+        // Another task wants the object, let the dweller be assigned to it
+        if q_tasks.iter().any(|(_, task, task_needs)| {
+            task.dweller.is_none()
+                && task.kind != TaskKind::Stockpile
+                && matches!(task_needs, TaskNeeds::Objects(_))
+                && dweller.can_do(task.kind, task_needs)
+        }) {
+            continue;
+        }
+
+        let dweller_pos = IVec2::new(
+            (transform.translation.x / TILE_SIZE) as i32,
+            (transform.translation.y / TILE_SIZE) as i32,
+        );
+
+        // Nearest tile with room of the highest priority zone accepting the object,
+        // stacks of the same object and quality are completed before new ones are started
+        let destination = zones
+            .iter()
+            .enumerate()
+            .filter(|(_, zone)| zone.accepts(object))
+            .find_map(|(zone_i, _)| {
+                free_positions[zone_i]
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(pos_i, pos)| {
+                        let tile = tilemap_data.get(*pos)?;
+                        let room = if tile.object.is_none() || tile.quality == quality {
+                            tile.room_for(object)
+                        } else {
+                            0
+                        };
+
+                        (room > 0 && tilemap_data.may_be_connected(dweller_pos, *pos)).then(|| {
+                            let distance =
+                                (dweller_pos.x - pos.x).abs() + (dweller_pos.y - pos.y).abs();
+                            (pos_i, room, (tile.object.is_none(), distance))
+                        })
+                    })
+                    .min_by_key(|(_, _, key)| *key)
+                    .map(|(pos_i, room, _)| (zone_i, pos_i, room))
+            });
+
+        let Some((zone_i, pos_i, room)) = destination else {
+            // Nowhere to store it, drop it next to the dweller, outside of zones
+            if let Some(pos) = TilemapData::find_from_center_chunk_size(dweller_pos, |pos| {
+                !tasks_positions.contains(&pos)
+                    && !zones.iter().any(|zone| zone.contains(pos))
+                    && tilemap_data.get(pos).is_some_and(TilePlaced::is_floor_free)
+            }) {
+                if let Some(tile) = tilemap_data.get(pos) {
+                    let count = carried.min(object.data().max_stack());
+                    dweller.inventory.take(object, count);
+                    tilemap_data.set(pos, tile.add_with_quality(object, count, quality));
+                    tasks_positions.insert(pos);
+
+                    debug!(
+                        "Dweller {} dropped {count} {object:?} at {pos:?}",
+                        dweller.name
+                    );
+                }
+            }
+
+            continue;
+        };
+
+        let pos = free_positions[zone_i].swap_remove(pos_i);
+        let count = carried.min(room);
+
+        commands.spawn(TaskBundle::new(
+            Task::new(
+                pos,
+                TaskKind::Stockpile,
+                Some(entity_dweller),
+                &tilemap_data,
+            )
+            .with_priority(haul_priority.0),
+            TaskNeeds::Objects(vec![object; usize::from(count)]),
+        ));
+
+        debug!(
+            "Dweller {} stores {count} {object:?} in {} at {pos:?}",
+            dweller.name, zones[zone_i].name
+        );
+    }
+
+    // Units of each object the zones can still take, counted when first needed
+    let mut room = HashMap::<(usize, ObjectId), u32>::new();
+
+    let mut haul = |pos: IVec2, object: ObjectId, count: u8, from: Option<usize>| {
+        // Zones are sorted, so the better zones come first
+        let Some(zone_i) = (0..zones.len()).find(|zone_i| {
+            zones[*zone_i].accepts(object)
+                && from.is_none_or(|from| {
+                    from != *zone_i
+                        && (!zones[from].accepts(object)
+                            || zones[*zone_i].priority > zones[from].priority)
+                })
+                && *room.entry((*zone_i, object)).or_insert_with(|| {
+                    free_positions[*zone_i]
+                        .iter()
+                        .filter_map(|pos| tilemap_data.get(*pos))
+                        .map(|tile| u32::from(tile.room_for(object)))
+                        .sum()
+                }) > 0
+                && tilemap_data.may_be_connected(pos, free_positions[*zone_i][0])
+        }) else {
+            return;
+        };
+
+        if let Some(room) = room.get_mut(&(zone_i, object)) {
+            *room = room.saturating_sub(u32::from(count.min(Inventory::slot_capacity(object))));
+        }
+
+        commands.spawn(TaskBundle::new(
+            Task::new(pos, TaskKind::Pickup, None, &tilemap_data)
+                .with_priority(haul_priority.0)
+                .with_category(Some(WorkCategory::Hauling)),
+            TaskNeeds::EmptyHands,
+        ));
+
+        debug!("Hauling {object:?} at {pos:?} to {}", zones[zone_i].name);
+    };
+
+    let haulable_object = |pos: IVec2| {
+        if tasks_positions.contains(&pos) {
+            return None;
+        }
+
+        // Claimed units are left for their task, hauling pickups take what remains
+        let tile = tilemap_data.get(pos)?;
+        let count = reservations.available(pos, tile);
+        tile.object
+            .filter(|object| {
+                count > 0 && is_haulable(*object) && TaskKind::Pickup.is_valid_on_tile(tile)
+            })
+            .map(|object| (object, count))
+    };
+
+    // Objects in zones that no longer allow them, or with a better zone
+    for (zone_i, zone) in zones.iter().enumerate() {
+        for pos in zone.positions() {
+            if let Some((object, count)) = haulable_object(pos) {
+                haul(pos, object, count, Some(zone_i));
+            }
+        }
+    }
+
+    // Loose objects outside of zones
+    let mut loose_positions = tilemap_data
+        .carriable_objects()
+        .filter(|pos| !zones.iter().any(|zone| zone.contains(*pos)))
+        .collect::<Vec<_>>();
+    loose_positions.sort_by_key(|pos| (pos.y, pos.x));
+
+    for pos in loose_positions {
+        if let Some((object, count)) = haulable_object(pos) {
+            haul(pos, object, count, None);
+        }
+    }
+}
+
+/// Dig a grave in a graveyard for each corpse lying around or being carried there.
+/// The corpses are fetched like the objects of any Build task.
+pub fn update_graveyards(
+    mut commands: Commands,
+    tilemap_data: Res<TilemapData>,
+    q_zones: Query<&StockpileZone>,
+    q_dwellers: Query<&Dweller>,
+    q_tasks: Query<&Task>,
+) {
+    const GRAVE: TaskKind = TaskKind::Build {
+        result: BuildResult::Object(ObjectId::Grave),
+    };
+
+    let graves = q_tasks.iter().filter(|task| task.kind == GRAVE).count();
+
+    let corpses = tilemap_data
+        .chunks
+        .values()
+        .flat_map(|chunk| chunk.iter().flatten())
+        .filter(|tile| tile.object == Some(ObjectId::Corpse))
+        .count()
+        + q_dwellers
+            .iter()
+            .map(|dweller| usize::from(dweller.inventory.count(ObjectId::Corpse)))
+            .sum::<usize>();
+
+    if corpses <= graves {
+        return;
+    }
+
+    // Highest priority first
+    let mut graveyards = q_zones
+        .iter()
+        .filter(|zone| zone.graveyard)
+        .collect::<Vec<_>>();
+    graveyards.sort_by_key(|zone| -zone.priority);
+
+    let tasks_positions = q_tasks.iter().map(|task| task.pos).collect::<HashSet<_>>();
+
+    let free_positions = graveyards
+        .iter()
+        .flat_map(|zone| zone.positions())
+        .filter(|pos| {
+            !tasks_positions.contains(pos)
+                && tilemap_data
+                    .get(*pos)
+                    .is_some_and(|tile| GRAVE.is_valid_on_tile(tile))
+        })
+        .take(corpses - graves);
+
+    for pos in free_positions {
+        commands.spawn(TaskBundle::new(
+            Task::new(pos, GRAVE, None, &tilemap_data),
+            TaskNeeds::Objects(vec![ObjectId::Corpse]),
+        ));
+
+        debug!("Grave dug at {pos:?}");
+    }
+}
+
+/// Overlay showing the zones
+pub fn spawn_stockpile_zones_sprite(
+    mut commands: Commands,
+    q_zones: Query<(Entity, &StockpileZone), Added<StockpileZone>>,
+) {
+    for (entity, zone) in &q_zones {
+        let size = (zone.max - zone.min + IVec2::ONE).as_vec2() * TILE_SIZE;
+        let color = if zone.graveyard {
+            Color::srgba(0.5, 0.5, 0.6, 0.3)
+        } else {
+            Color::srgba(0.9, 0.8, 0.3, 0.2)
+        };
+
+        commands.entity(entity).insert((
+            Sprite {
+                color,
+                custom_size: Some(size),
+                anchor: Anchor::BottomLeft,
+                ..default()
+            },
+            Transform::from_xyz(
+                zone.min.x as f32 * TILE_SIZE,
+                zone.min.y as f32 * TILE_SIZE,
+                Z_INDEX,
+            ),
+        ));
+    }
+}
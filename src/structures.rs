@@ -1,12 +1,57 @@
-use bevy::math::IVec2;
+use bevy::{math::IVec2, utils::HashMap};
+use serde::Deserialize;
 
 use crate::{data::MobId, TilePlaced};
 
+/// Structure as written in the definitions, the first row is the top one and spaces are no tile
+#[derive(Deserialize)]
+struct StructureDefinition {
+    legend: HashMap<char, TilePlaced>,
+    tiles: Vec<String>,
+    mobs: Vec<((u32, u32), MobId)>,
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "StructureDefinition")]
 pub struct StructureData {
     tiles: Vec<Vec<Option<TilePlaced>>>,
     mobs: Vec<(IVec2, MobId)>,
 }
 
+impl TryFrom<StructureDefinition> for StructureData {
+    type Error = String;
+
+    fn try_from(definition: StructureDefinition) -> Result<Self, Self::Error> {
+        let tiles = definition
+            .tiles
+            .iter()
+            .map(|row| {
+                row.chars()
+                    .map(|c| match c {
+                        ' ' => Ok(None),
+                        c => definition
+                            .legend
+                            .get(&c)
+                            .copied()
+                            .map(Some)
+                            .ok_or_else(|| format!("Character '{c}' is not in the legend")),
+                    })
+                    .collect()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let structure = StructureData::new(tiles, definition.mobs);
+
+        for (pos, mob) in structure.mobs() {
+            if structure.get_tile(pos.x as usize, pos.y as usize).is_none() {
+                return Err(format!("{mob:?} at {pos} is not on a tile"));
+            }
+        }
+
+        Ok(structure)
+    }
+}
+
 impl StructureData {
     pub fn new(mut tiles: Vec<Vec<Option<TilePlaced>>>, mobs: Vec<((u32, u32), MobId)>) -> Self {
         tiles.reverse();
@@ -1,20 +1,27 @@
-use std::time::{SystemTime, UNIX_EPOCH};
-
 use bevy::{
-    ecs::{entity::MapEntities, reflect::ReflectMapEntities},
+    ecs::{
+        component::ComponentId, entity::MapEntities, reflect::ReflectMapEntities,
+        world::DeferredWorld,
+    },
     prelude::*,
-    utils::hashbrown::HashSet,
+    utils::hashbrown::{HashMap, HashSet},
 };
-use dashmap::DashSet;
-use pathfinding::directed::astar::astar;
 use rand::Rng;
+use serde::Deserialize;
 
 use crate::{
-    data::{ObjectId, TileId, WORKSTATIONS},
-    dwellers::Dweller,
+    combat::in_melee_range,
+    data::{ObjectId, TileId, DEFINITIONS},
+    dwellers::{Dweller, WorkCategory},
+    dwellers_inventory::Inventory,
     dwellers_needs::DwellerNeeds,
+    dwellers_skills::{DwellerSkills, Skill},
     mobs::Mob,
-    tilemap::{CHUNK_SIZE, TILE_SIZE},
+    mood::ColonyMood,
+    reservations::{Reservation, Reservations},
+    simulation::{SimulationRng, SimulationStats},
+    stockpiles::is_haulable,
+    tilemap::TILE_SIZE,
     tilemap_data::TilemapData,
     tiles::TilePlaced,
     ObjectSlot, SpriteLoader,
@@ -22,7 +29,10 @@ use crate::{
 
 const Z_INDEX: f32 = 2.0;
 
-#[derive(PartialEq, Clone, Copy, Reflect, Default, Debug)]
+/// Work points applied each time a dweller with an average skill works on a task
+pub const WORK_PER_TICK: u32 = 10;
+
+#[derive(PartialEq, Clone, Copy, Reflect, Default, Deserialize, Debug)]
 pub enum TaskKind {
     #[default]
     Dig,
@@ -34,14 +44,16 @@ pub enum TaskKind {
     Build {
         result: BuildResult,
     },
+    /// `recipe` is the one being crafted, the next ones are in the `WorkstationBills`
     Workstation {
-        amount: u32,
+        recipe: usize,
     },
     Walk,
     UseToSatisfyNeed,
 }
 
 impl TaskKind {
+    #[must_use]
     pub fn is_valid_on_tile(self, tile: TilePlaced) -> bool {
         match self {
             TaskKind::Dig => matches!(
@@ -73,15 +85,13 @@ impl TaskKind {
                         .is_some_and(|object| object.data().is_carriable())
             }
             TaskKind::Hunt => true,
-            TaskKind::Stockpile => {
-                !tile.id.data().is_wall()
-                    && tile
-                        .object
-                        .is_none_or(|object| object.data().is_carriable())
-            }
+            // Empty, or a stack with room left
+            TaskKind::Stockpile => tile
+                .object
+                .map_or(!tile.is_blocking(), |object| tile.room_for(object) > 0),
             TaskKind::Workstation { .. } => tile
                 .object
-                .is_some_and(|object| WORKSTATIONS.contains_key(&object)),
+                .is_some_and(|object| DEFINITIONS.workstations.contains_key(&object)),
             TaskKind::Walk => !tile.is_blocking(),
             TaskKind::UseToSatisfyNeed => {
                 matches!(tile.object, Some(ObjectId::Bed | ObjectId::Bread))
@@ -89,27 +99,47 @@ impl TaskKind {
         }
     }
 
+    /// Work points needed to complete the task, 0 means instant completion
+    #[must_use]
+    pub fn work(self) -> u32 {
+        match self {
+            TaskKind::Dig => 100,
+            TaskKind::Smoothen => 60,
+            TaskKind::Harvest => 40,
+            TaskKind::Build { result } => result.work(),
+            TaskKind::Workstation { .. } => 80,
+            TaskKind::Pickup
+            | TaskKind::Hunt
+            | TaskKind::Stockpile
+            | TaskKind::Walk
+            | TaskKind::UseToSatisfyNeed => 0,
+        }
+    }
+
+    #[must_use]
     pub fn id(self) -> String {
         format!("{self:?}")
             .to_lowercase()
             .split_whitespace()
             .next()
-            .unwrap()
+            .unwrap_or_default()
             .to_string()
     }
 
+    #[must_use]
     pub fn sprite_path(self) -> String {
         format!("tasks/{}.png", self.id())
     }
 }
 
-#[derive(PartialEq, Clone, Copy, Reflect, Debug)]
+#[derive(PartialEq, Clone, Copy, Reflect, Deserialize, Debug)]
 pub enum BuildResult {
     Object(ObjectId),
     Tile(TileId),
 }
 
 impl BuildResult {
+    #[must_use]
     pub fn sprite_path(self) -> String {
         match self {
             BuildResult::Object(object) => object.data().sprite_path(),
@@ -117,6 +147,18 @@ impl BuildResult {
         }
     }
 
+    #[must_use]
+    pub fn work(self) -> u32 {
+        let ingredients = DEFINITIONS
+            .build_recipes
+            .iter()
+            .find(|(result, _)| *result == self)
+            .map_or(1, |(_, cost)| cost.len());
+
+        40 + 30 * ingredients as u32
+    }
+
+    #[must_use]
     pub fn debug_name(self) -> String {
         match self {
             BuildResult::Object(object) => format!("{object:?}"),
@@ -135,6 +177,7 @@ pub struct TaskBundle {
 }
 
 impl TaskBundle {
+    #[must_use]
     pub fn new(task: Task, needs: TaskNeeds) -> Self {
         let x = task.pos.x as f32 * TILE_SIZE;
         let y = task.pos.y as f32 * TILE_SIZE;
@@ -142,6 +185,7 @@ impl TaskBundle {
         Self::new_inner(task, needs, x, y)
     }
 
+    #[must_use]
     pub fn new_as_child(task: Task, needs: TaskNeeds) -> Self {
         Self::new_inner(task, needs, 0.0, 0.0)
     }
@@ -159,19 +203,76 @@ impl TaskBundle {
     }
 }
 
-#[derive(Component, Reflect, PartialEq, Clone, Default, Debug)]
+#[derive(Component, Reflect, PartialEq, Clone, Default, Deserialize, Debug)]
 #[reflect(Component)]
 pub enum TaskNeeds {
     #[default]
     Nothing,
+    /// A free inventory slot, to pick something up
     EmptyHands,
     Objects(Vec<ObjectId>),
-    AnyObject,
     Impossible,
 }
 
+/// Ordered crafting queue of a Workstation task, the first bill is crafted first
+#[derive(Component, Reflect, PartialEq, Clone, Default, Debug)]
+#[reflect(Component)]
+pub struct WorkstationBills(pub Vec<Bill>);
+
+#[derive(Reflect, PartialEq, Clone, Copy, Deserialize, Debug)]
+pub struct Bill {
+    /// Index in the workstation recipes
+    pub recipe: usize,
+    pub amount: u32,
+    #[serde(default)]
+    pub mode: BillMode,
+}
+
+#[derive(Reflect, PartialEq, Clone, Copy, Default, Deserialize, Debug)]
+pub enum BillMode {
+    /// Craft `amount` times, then remove the bill
+    #[default]
+    Repeat,
+    /// Craft whenever the colony holds less than `amount` of the output
+    UntilStock,
+}
+
+impl Bill {
+    #[must_use]
+    pub fn is_active(&self, output: ObjectId, stock: &ObjectsStock) -> bool {
+        match self.mode {
+            BillMode::Repeat => self.amount > 0,
+            BillMode::UntilStock => stock.get(output) < self.amount,
+        }
+    }
+}
+
+impl WorkstationBills {
+    /// Count a crafted recipe, removing its bill when done
+    fn complete(&mut self, recipe: usize) {
+        if let Some(i) = self
+            .0
+            .iter()
+            .position(|bill| bill.recipe == recipe && bill.mode == BillMode::Repeat)
+        {
+            self.0[i].amount = self.0[i].amount.saturating_sub(1);
+
+            if self.0[i].amount == 0 {
+                self.0.remove(i);
+            }
+        }
+    }
+}
+
+/// Monotonic task ids, given in spawn order so that task ordering is reproducible
+#[derive(Resource, Default)]
+pub struct TaskIds {
+    last: u64,
+}
+
 #[derive(Component, Reflect, Default, Debug)]
 #[reflect(Component, MapEntities)]
+#[component(on_add = assign_task_id)]
 pub struct Task {
     id: u64,
     pub kind: TaskKind,
@@ -180,6 +281,32 @@ pub struct Task {
     pub reachable_positions: Vec<IVec2>,
     pub dweller: Option<Entity>,
     pub priority: i32,
+    pub progress: u32,
+    /// Work category of a Pickup, the one of the task needing the object or Hauling.
+    /// Other tasks are categorized by their kind
+    pub category: Option<WorkCategory>,
+}
+
+/// New tasks get the next id, loaded tasks keep theirs and the following ids start after them
+fn assign_task_id(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    let Some(id) = world.get::<Task>(entity).map(|task| task.id) else {
+        return;
+    };
+
+    let Some(mut task_ids) = world.get_resource_mut::<TaskIds>() else {
+        return;
+    };
+
+    if id == 0 {
+        task_ids.last += 1;
+        let id = task_ids.last;
+
+        if let Some(mut task) = world.get_mut::<Task>(entity) {
+            task.id = id;
+        }
+    } else {
+        task_ids.last = task_ids.last.max(id);
+    }
 }
 
 impl MapEntities for Task {
@@ -211,6 +338,7 @@ impl PartialEq for Task {
 impl Eq for Task {}
 
 impl Task {
+    #[must_use]
     pub fn new(
         pos: IVec2,
         kind: TaskKind,
@@ -218,26 +346,37 @@ impl Task {
         tilemap_data: &TilemapData,
     ) -> Self {
         let mut task = Self {
-            id: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_nanos() as u64,
+            id: 0, // assigned on spawn
             kind,
             pos,
             reachable_pathfinding: true,
             reachable_positions: vec![],
             dweller,
             priority: 0,
+            progress: 0,
+            category: None,
         };
         task.recompute_reachable_positions(tilemap_data);
         task
     }
 
+    #[must_use]
     pub fn with_priority(mut self, priority: i32) -> Self {
         self.priority = priority;
         self
     }
 
+    #[must_use]
+    pub fn with_category(mut self, category: Option<WorkCategory>) -> Self {
+        self.category = category;
+        self
+    }
+
+    #[must_use]
+    pub fn is_work_done(&self) -> bool {
+        self.progress >= self.kind.work()
+    }
+
     pub fn recompute_reachable_positions(&mut self, tilemap_data: &TilemapData) {
         self.reachable_positions = self.compute_reachable_positions(self.pos, tilemap_data);
     }
@@ -261,28 +400,85 @@ impl Task {
 
         tilemap_data.non_blocking_neighbours_pos(pos, true)
     }
+}
 
-    pub fn pathfind(
-        &self,
-        dweller_pos: IVec2,
-        tilemap_data: &TilemapData,
-    ) -> Option<(Vec<IVec2>, i32)> {
-        self.reachable_positions
-            .iter()
-            .filter_map(|pos| {
-                astar(
-                    pos,
-                    |p| {
-                        tilemap_data
-                            .non_blocking_neighbours_pos(*p, true)
-                            .into_iter()
-                            .map(|p| (p, 1))
-                    },
-                    |p| (p.x - dweller_pos.x).abs() + (p.y - dweller_pos.y).abs(),
-                    |p| *p == dweller_pos,
-                )
-            })
-            .min_by_key(|path| path.1)
+/// Objects lying on loaded tiles or carried by dwellers, for `BillMode::UntilStock`
+#[derive(Resource, Default)]
+pub struct ObjectsStock(HashMap<ObjectId, u32>);
+
+impl ObjectsStock {
+    #[must_use]
+    pub fn get(&self, object: ObjectId) -> u32 {
+        self.0.get(&object).copied().unwrap_or(0)
+    }
+}
+
+pub fn update_objects_stock(
+    mut stock: ResMut<ObjectsStock>,
+    tilemap_data: Res<TilemapData>,
+    q_dwellers: Query<&Dweller>,
+) {
+    stock.0.clear();
+
+    let objects = tilemap_data
+        .chunks
+        .values()
+        .flat_map(|chunk| {
+            chunk
+                .iter()
+                .flatten()
+                .filter_map(|tile| Some((tile.object?, tile.count)))
+        })
+        .chain(
+            q_dwellers
+                .iter()
+                .flat_map(|dweller| dweller.inventory.totals()),
+        );
+
+    for (object, count) in objects {
+        *stock.0.entry(object).or_default() += u32::from(count);
+    }
+}
+
+/// Start the first active bill of idle workstations, ingredients already brought keep their recipe
+pub fn update_workstations_bills(
+    tilemap_data: Res<TilemapData>,
+    stock: Res<ObjectsStock>,
+    mut q_tasks: Query<(&mut Task, &mut TaskNeeds, &WorkstationBills)>,
+) {
+    for (mut task, mut task_needs, bills) in &mut q_tasks {
+        let TaskKind::Workstation { recipe } = task.kind else {
+            continue;
+        };
+
+        let Some(workstation) = tilemap_data.get(task.pos).and_then(|tile| tile.object) else {
+            continue;
+        };
+
+        let in_progress = task.dweller.is_some()
+            || task.progress > 0
+            || matches!(&*task_needs, TaskNeeds::Objects(objects)
+                if DEFINITIONS.recipe(workstation, recipe)
+                    .is_some_and(|recipe| objects.len() < recipe.ingredients.len()));
+
+        if in_progress {
+            continue;
+        }
+
+        let next = bills.0.iter().find_map(|bill| {
+            let recipe = DEFINITIONS.recipe(workstation, bill.recipe)?;
+            bill.is_active(recipe.output, &stock)
+                .then_some((bill.recipe, recipe))
+        });
+
+        if let Some((index, next)) = next {
+            if recipe != index {
+                task.kind = TaskKind::Workstation { recipe: index };
+            }
+            task_needs.set_if_neq(TaskNeeds::Objects(next.ingredients.clone()));
+        } else {
+            task_needs.set_if_neq(TaskNeeds::Impossible);
+        }
     }
 }
 
@@ -296,6 +492,12 @@ pub fn update_unreachable_tasks(tilemap_data: Res<TilemapData>, mut q_tasks: Que
     }
 }
 
+pub fn update_pathfinding_caches(mut tilemap_data: ResMut<TilemapData>) {
+    if tilemap_data.are_pathfinding_caches_dirty() {
+        tilemap_data.rebuild_pathfinding_caches();
+    }
+}
+
 pub fn update_unreachable_pathfinding_tasks(mut q_tasks: Query<&mut Task>) {
     q_tasks.par_iter_mut().for_each(|mut task| {
         if !task.reachable_pathfinding {
@@ -313,15 +515,25 @@ pub fn event_task_completion(
     mut commands: Commands,
     mut events: EventReader<TaskCompletionEvent>,
     mut tilemap_data: ResMut<TilemapData>,
-    q_mobs: Query<(Entity, &Mob, &Transform)>,
-    mut q_dwellers: Query<(&mut Dweller, &mut DwellerNeeds, &Transform)>,
+    reservations: Res<Reservations>,
+    q_mobs: Query<&Transform, With<Mob>>,
+    mut q_dwellers: Query<(
+        &mut Dweller,
+        &mut DwellerNeeds,
+        &mut DwellerSkills,
+        &Transform,
+    )>,
     mut q_tasks: Query<(Entity, &mut Task, &mut TaskNeeds, Option<&Parent>)>,
+    mut q_bills: Query<&mut WorkstationBills>,
+    mut rng: ResMut<SimulationRng>,
+    mut stats: ResMut<SimulationStats>,
+    mood: Res<ColonyMood>,
 ) {
-    let mut rng = rand::rng();
-
     let mut update_tasks_pos = false;
-    let mut update_stockpiles = false;
     let mut update_workstations = false;
+    let mut objects_taken = false;
+    // Tasks despawned in this system, they are still returned by the queries
+    let mut removed_tasks = HashSet::new();
 
     let tasks_positions = q_tasks
         .iter()
@@ -334,7 +546,7 @@ pub fn event_task_completion(
             continue;
         };
 
-        let Some((mut dweller, mut dweller_needs, dweller_transform)) =
+        let Some((mut dweller, mut dweller_needs, mut dweller_skills, dweller_transform)) =
             task.dweller.and_then(|d| q_dwellers.get_mut(d).ok())
         else {
             continue;
@@ -346,19 +558,46 @@ pub fn event_task_completion(
 
         let mut success = false;
 
+        // The object was taken earlier in this loop, the task is removed below
+        if objects_taken
+            && matches!(task.kind, TaskKind::Pickup | TaskKind::UseToSatisfyNeed)
+            && !task.kind.is_valid_on_tile(tile)
+        {
+            continue;
+        }
+
         // just to be sure
         if !task.kind.is_valid_on_tile(tile) {
             error!("SHOULD NEVER HAPPEN: removing invalid task {task:?} on tile {tile:?}");
-            commands.entity(entity).despawn_recursive();
+            if removed_tasks.insert(entity) {
+                commands.entity(entity).despawn_recursive();
+            }
             continue;
         }
 
+        // The inventory covers every remaining object
         let about_to_finish = match &*task_needs {
-            TaskNeeds::Objects(objects) => objects.len() == 1,
+            TaskNeeds::Objects(objects) => dweller.inventory.covers(objects),
             TaskNeeds::Impossible => false,
             _ => true,
         };
 
+        // Work on the task until enough progress has been made.
+        // Progress is kept on the task, so another dweller can resume it.
+        let directly_built = matches!(
+            task.kind,
+            TaskKind::Build {
+                result: BuildResult::Object(object),
+            } if dweller.inventory.contains(object)
+        );
+
+        let skill = Skill::for_task(task.kind, tile);
+
+        if about_to_finish && !directly_built && !task.is_work_done() {
+            task.progress += mood.work_per_tick(dweller_skills.work_per_tick(skill));
+            continue;
+        }
+
         // Apply task, set success to true
         // if success, TaskNeeds are handled after
         match task.kind {
@@ -371,12 +610,8 @@ pub fn event_task_completion(
                     None
                 };
 
+                // Dropped objects are hauled by update_hauling
                 let tile = if let Some(object) = object {
-                    commands.spawn(TaskBundle::new(
-                        Task::new(task.pos, TaskKind::Pickup, None, &tilemap_data),
-                        TaskNeeds::EmptyHands,
-                    ));
-
                     TileId::StoneFloor.with(object)
                 } else {
                     TileId::StoneFloor.place()
@@ -394,10 +629,11 @@ pub fn event_task_completion(
             TaskKind::Smoothen => {
                 let tile = if tile.id.data().is_wall() {
                     TileId::DungeonWall.place()
-                } else if let Some(object) = tile.object {
-                    TileId::DungeonFloor.with(object)
                 } else {
-                    TileId::DungeonFloor.place()
+                    TilePlaced {
+                        id: TileId::DungeonFloor,
+                        ..tile
+                    }
                 };
 
                 tilemap_data.set(task.pos, tile);
@@ -420,11 +656,12 @@ pub fn event_task_completion(
                         ObjectId::TallGrass => Some(ObjectId::Seeds),
 
                         ObjectId::WheatPlant => {
-                            dweller.object = Some(if rng.random_bool(0.7) {
+                            let object = if rng.random_bool(0.7) {
                                 ObjectId::Wheat
                             } else {
                                 ObjectId::Seeds
-                            });
+                            };
+                            dweller.inventory.add(object, 1);
 
                             if rng.random_bool(0.1) {
                                 for (pos, tile) in tilemap_data.neighbours(task.pos) {
@@ -443,13 +680,6 @@ pub fn event_task_completion(
 
                     if let Some(object) = drop_object {
                         tilemap_data.set(task.pos, tile.id.with(object));
-
-                        if object.data().is_carriable() {
-                            commands.spawn(TaskBundle::new(
-                                Task::new(task.pos, TaskKind::Pickup, None, &tilemap_data),
-                                TaskNeeds::EmptyHands,
-                            ));
-                        }
                     } else {
                         tilemap_data.set(task.pos, tile.id.place());
                     }
@@ -464,33 +694,60 @@ pub fn event_task_completion(
 
             TaskKind::Pickup => {
                 if let Some(object) = tile.object {
-                    tilemap_data.set(task.pos, tile.id.place());
+                    // Units claimed by other tasks stay on the tile
+                    let available = tile.count.saturating_sub(reservations.reserved(
+                        task.pos,
+                        object,
+                        Some(entity),
+                    ));
+
+                    let picked_up = match (object.data().slot(), dweller.tool, dweller.armor) {
+                        _ if available == 0 => 0,
 
-                    match (object.data().slot(), dweller.tool, dweller.armor) {
                         (ObjectSlot::Tool, None, _) => {
-                            dweller.tool = Some(object);
+                            dweller.tool = Some((object, tile.quality));
                             debug!("Picked up tool {:?} at {:?}", object, task.pos);
+                            1
                         }
 
                         (ObjectSlot::Armor, _, None) => {
-                            dweller.armor = Some(object);
+                            dweller.armor = Some((object, tile.quality));
                             debug!("Picked up armor {:?} at {:?}", object, task.pos);
+                            1
                         }
 
+                        // The rest of the stack stays on the tile
                         _ => {
-                            dweller.object = Some(object);
-                            debug!("Picked up object {:?} at {:?}", object, task.pos);
+                            let count =
+                                dweller
+                                    .inventory
+                                    .add_with_quality(object, available, tile.quality);
+                            debug!("Picked up {count} {:?} at {:?}", object, task.pos);
+                            count
                         }
-                    }
+                    };
 
-                    if object.data().is_blocking() {
-                        update_tasks_pos = true;
-                    }
-                    update_stockpiles = true;
-                    if WORKSTATIONS.contains_key(&object) {
-                        update_workstations = true;
+                    if picked_up > 0 {
+                        tilemap_data.set(task.pos, tile.take(picked_up));
+
+                        if object.data().is_blocking() {
+                            update_tasks_pos = true;
+                        }
+                        objects_taken = true;
+                        if DEFINITIONS.workstations.contains_key(&object) {
+                            update_workstations = true;
+                        }
+                        success = true;
+                    } else if available == 0 {
+                        // Everything left is claimed, another task will take it
+                        debug!("Nothing left to pick up at {:?}", task.pos);
+                        if removed_tasks.insert(entity) {
+                            commands.entity(entity).despawn_recursive();
+                        }
+                    } else {
+                        // No room left, let another dweller pick it up
+                        task.dweller = None;
                     }
-                    success = true;
                 }
             }
 
@@ -501,7 +758,7 @@ pub fn event_task_completion(
                 //FIXME: if TaskNeeds objects have already been consumed, the task should not be completable with the goal object (as it would waste the needed objects).
                 if !about_to_finish
                     && !match result {
-                        BuildResult::Object(object) => dweller.object == Some(object),
+                        BuildResult::Object(object) => dweller.inventory.contains(object),
                         _ => false,
                     }
                 {
@@ -511,15 +768,23 @@ pub fn event_task_completion(
                         BuildResult::Object(object) => {
                             tilemap_data.set(task.pos, tile.id.with(object));
 
-                            if let Some(workstation) = WORKSTATIONS.get(&object) {
-                                commands.spawn(TaskBundle::new(
-                                    Task::new(
-                                        task.pos,
-                                        TaskKind::Workstation { amount: 1 },
-                                        None,
-                                        &tilemap_data,
+                            if DEFINITIONS.workstations.contains_key(&object) {
+                                // Needs are set from the bills by update_workstations_bills
+                                commands.spawn((
+                                    TaskBundle::new(
+                                        Task::new(
+                                            task.pos,
+                                            TaskKind::Workstation { recipe: 0 },
+                                            None,
+                                            &tilemap_data,
+                                        ),
+                                        TaskNeeds::Impossible,
                                     ),
-                                    TaskNeeds::Objects(workstation.1.clone()),
+                                    WorkstationBills(vec![Bill {
+                                        recipe: 0,
+                                        amount: 1,
+                                        mode: BillMode::Repeat,
+                                    }]),
                                 ));
                             }
                         }
@@ -539,37 +804,13 @@ pub fn event_task_completion(
 
             TaskKind::Hunt => {
                 if let Some(task_parent) = task_parent.map(Parent::get) {
-                    if let Ok((entity_mob, mob, mob_transform)) = q_mobs.get(task_parent) {
+                    if let Ok(mob_transform) = q_mobs.get(task_parent) {
                         let mob_pos = (mob_transform.translation / TILE_SIZE)
                             .truncate()
                             .as_ivec2();
 
-                        if dweller_transform
-                            .translation
-                            .distance(mob_transform.translation)
-                            < TILE_SIZE
-                        {
-                            if let Some(loot_tile) = tilemap_data.get(mob_pos) {
-                                if loot_tile.object.is_none() {
-                                    tilemap_data.set(mob_pos, loot_tile.id.with(mob.loot));
-
-                                    commands.spawn(TaskBundle::new(
-                                        Task::new(mob_pos, TaskKind::Pickup, None, &tilemap_data),
-                                        TaskNeeds::EmptyHands,
-                                    ));
-                                } else {
-                                    debug!("Hunted mob at {:?} but loot tile is occupied", mob_pos);
-                                }
-                            }
-
-                            commands.entity(entity_mob).despawn_recursive();
-
-                            dweller_needs.sleep(-5);
-                            dweller_needs.food(-5);
-
-                            debug!("Hunted mob at {:?}", mob_transform.translation);
-                            success = true;
-                        } else {
+                        // At melee range the fight goes on in update_combat, until the mob dies
+                        if !in_melee_range(dweller_transform, mob_transform) {
                             task.pos = mob_pos;
                             task.recompute_reachable_positions(&tilemap_data);
                         }
@@ -578,39 +819,48 @@ pub fn event_task_completion(
             }
 
             TaskKind::Stockpile => {
-                if tile.object.is_none() {
-                    if let Some(object) = dweller.object {
-                        tilemap_data.set(task.pos, tile.id.with(object));
+                if let TaskNeeds::Objects(objects) = &*task_needs {
+                    // Carried units are consumed with the TaskNeeds below
+                    let count = u8::try_from(objects.len()).unwrap_or(u8::MAX);
 
-                        debug!("Stockpiled object at {:?}", task.pos);
+                    if let Some(&object) = objects.first().filter(|object| {
+                        dweller.inventory.covers(objects) && tile.room_for(**object) >= count
+                    }) {
+                        let quality = dweller.inventory.quality(object, count);
+                        tilemap_data.set(task.pos, tile.add_with_quality(object, count, quality));
+
+                        debug!("Stockpiled {count} {object:?} at {:?}", task.pos);
                         update_tasks_pos = true;
                         success = true;
                     }
                 }
             }
 
-            TaskKind::Workstation { .. } => {
-                if let Some(recipe) = tile.object.and_then(|object| WORKSTATIONS.get(&object)) {
+            TaskKind::Workstation { recipe } => {
+                if let Some(recipe) = tile
+                    .object
+                    .and_then(|object| DEFINITIONS.recipe(object, recipe))
+                {
                     if about_to_finish {
-                        for (pos, tile) in tilemap_data.neighbours(task.pos) {
-                            if tile.is_floor_free() && !tasks_positions.contains(&pos) {
-                                tilemap_data.set(pos, tile.id.with(recipe.0));
-
-                                if recipe.0.data().is_carriable() {
-                                    commands.spawn(TaskBundle::new(
-                                        Task::new(pos, TaskKind::Pickup, None, &tilemap_data),
-                                        TaskNeeds::EmptyHands,
-                                    ));
-                                }
+                        // Skilled dwellers craft better outputs
+                        let quality = dweller_skills.output_quality(skill);
 
-                                dweller_needs.sleep(-1);
-                                dweller_needs.food(-1);
+                        // The output is stacked on a neighbouring tile with room left
+                        for (pos, tile) in tilemap_data.neighbours(task.pos) {
+                            if tile.room_for(recipe.output) > 0 && !tasks_positions.contains(&pos) {
+                                tilemap_data
+                                    .set(pos, tile.add_with_quality(recipe.output, 1, quality));
 
-                                debug!("Workstation output at {:?}", pos);
+                                debug!("Workstation output of quality {quality} at {:?}", pos);
                                 success = true;
                                 break;
                             }
                         }
+
+                        if success {
+                            dweller_needs.sleep(-1);
+                            dweller_needs.food(-1);
+                        }
                     } else {
                         debug!("Progressing workstation task {:?}", task);
                         success = true;
@@ -626,8 +876,13 @@ pub fn event_task_completion(
                 if let Some(object) = tile.object {
                     match object {
                         ObjectId::Bread => {
-                            tilemap_data.set(task.pos, tile.id.place());
+                            // Keep a ration for later if there is some left
+                            let ration =
+                                u8::from(tile.count > 1 && dweller.inventory.keep_ration());
+
+                            tilemap_data.set(task.pos, tile.take(1 + ration));
                             dweller_needs.food(500);
+                            objects_taken = true;
 
                             debug!("Ate bread {:?}", dweller_needs);
                             success = true;
@@ -651,37 +906,37 @@ pub fn event_task_completion(
         if success {
             let mut remove_task = true;
 
+            if about_to_finish {
+                stats.tasks_done += 1;
+
+                if let Some(skill) = skill {
+                    dweller_skills.gain_xp(skill, task.kind.work());
+                }
+            }
+
             match *task_needs {
                 TaskNeeds::Objects(ref mut objects) => {
-                    if let Some(dweller_object) = dweller.object {
-                        if matches!(
-                            task.kind,
-                            TaskKind::Build {
-                                result: BuildResult::Object(build_object),
-                                ..
-                            } if build_object == dweller_object
-                        ) {
-                            dweller.object = None;
-                            remove_task = true;
-                        } else if let Some(i) =
-                            objects.iter().position(|object| *object == dweller_object)
-                        {
-                            dweller.object = None;
-                            objects.swap_remove(i);
-                            remove_task = objects.is_empty();
-                        } else {
-                            error!("SHOULD NEVER HAPPEN: Dweller {} completed task TaskNeeds::Objects {:?} with object {:?} not in list", dweller.name, task.kind, dweller_object);
+                    match task.kind {
+                        TaskKind::Build {
+                            result: BuildResult::Object(build_object),
+                        } if directly_built => {
+                            dweller.inventory.take(build_object, 1);
                         }
-                    } else {
-                        error!("SHOULD NEVER HAPPEN: Dweller {} completed task TaskNeeds::Objects {:?} without any object", dweller.name, task.kind);
-                    }
-                }
 
-                TaskNeeds::AnyObject => {
-                    if dweller.object.is_some() {
-                        dweller.object = None;
-                    } else {
-                        error!("SHOULD NEVER HAPPEN: Dweller {} completed task {:?} without any object", dweller.name, task);
+                        _ => {
+                            // Deliver every needed object carried, the dweller keeps the rest
+                            let mut delivered = false;
+                            objects.retain(|object| {
+                                let taken = dweller.inventory.take(*object, 1) > 0;
+                                delivered |= taken;
+                                !taken
+                            });
+
+                            if !delivered {
+                                error!("SHOULD NEVER HAPPEN: Dweller {} completed task TaskNeeds::Objects {:?} without any needed object", dweller.name, task.kind);
+                            }
+                            remove_task = objects.is_empty();
+                        }
                     }
                 }
 
@@ -696,30 +951,26 @@ pub fn event_task_completion(
                 TaskNeeds::Nothing | TaskNeeds::EmptyHands => {}
             }
 
+            let task = &mut *task;
+
             // Do not remove permanent tasks
-            match task.kind {
-                TaskKind::Stockpile => {
+            if let TaskKind::Workstation { recipe } = task.kind {
+                if remove_task {
+                    if let Ok(mut bills) = q_bills.get_mut(entity) {
+                        bills.complete(recipe);
+                    }
+
+                    // The next recipe is picked by update_workstations_bills
                     *task_needs = TaskNeeds::Impossible;
+                    task.progress = 0;
                     remove_task = false;
                 }
-
-                TaskKind::Workstation { ref mut amount } => {
-                    if remove_task {
-                        if let Some(recipe) =
-                            tile.object.and_then(|object| WORKSTATIONS.get(&object))
-                        {
-                            *amount = amount.saturating_sub(1);
-                            *task_needs = TaskNeeds::Objects(recipe.1.clone());
-                        }
-                        remove_task = false;
-                    }
-                }
-
-                _ => {}
             }
 
             if remove_task {
-                commands.entity(entity).despawn_recursive();
+                if removed_tasks.insert(entity) {
+                    commands.entity(entity).despawn_recursive();
+                }
             } else {
                 task.dweller = None;
             }
@@ -732,26 +983,28 @@ pub fn event_task_completion(
         }
     }
 
-    // Set Stockpile tasks to AnyObject if there is nothing on the tile
-    if update_stockpiles {
-        for (_, task, mut task_needs, _) in &mut q_tasks {
-            if matches!(task.kind, TaskKind::Stockpile)
+    // Remove Workstation tasks if the workstation is gone
+    if update_workstations {
+        for (entity, task, _, _) in &q_tasks {
+            if matches!(task.kind, TaskKind::Workstation { .. })
                 && tilemap_data
                     .get(task.pos)
                     .is_some_and(TilePlaced::is_floor_free)
+                && removed_tasks.insert(entity)
             {
-                *task_needs = TaskNeeds::AnyObject;
+                commands.entity(entity).despawn_recursive();
             }
         }
     }
 
-    // Remove Workstation tasks if the workstation is gone
-    if update_workstations {
+    // Remove Pickup and UseToSatisfyNeed tasks if their object is gone
+    if objects_taken {
         for (entity, task, _, _) in &q_tasks {
-            if matches!(task.kind, TaskKind::Workstation { .. })
+            if matches!(task.kind, TaskKind::Pickup | TaskKind::UseToSatisfyNeed)
                 && tilemap_data
                     .get(task.pos)
-                    .is_some_and(TilePlaced::is_floor_free)
+                    .is_some_and(|tile| !task.kind.is_valid_on_tile(tile))
+                && removed_tasks.insert(entity)
             {
                 commands.entity(entity).despawn_recursive();
             }
@@ -760,11 +1013,19 @@ pub fn event_task_completion(
 }
 
 pub fn update_pickups(
-    par_commands: ParallelCommands,
+    mut commands: Commands,
     tilemap_data: Res<TilemapData>,
+    mut reservations: ResMut<Reservations>,
     q_tasks: Query<(Ref<Task>, Ref<TaskNeeds>)>,
     q_dwellers: Query<(Entity, &Dweller)>,
 ) {
+    // Closure result enum
+    enum TryFindObjectResult {
+        Wait,
+        Found,
+        NotFound,
+    }
+
     // FIXME: task.is_changed() || task_needs.is_changed() seems to always return true
     let mut updated = false;
 
@@ -791,14 +1052,14 @@ pub fn update_pickups(
         return;
     }
 
-    // Precompute dwellers with an object, not working on a task that needs it
+    // Precompute objects carried by dwellers, not working on a task that needs them
     let mut dwellers_candidates = HashSet::new();
     for (entity_dweller, dweller) in &q_dwellers {
-        if let Some(object) = dweller.object {
+        for (object, _) in dweller.inventory.stacks() {
             let not_working_on_task_that_needs_it =
                 !q_tasks.iter().any(|(t, tn)| {
                     t.dweller == Some(entity_dweller)
-                        && matches!(tn.into_inner(), TaskNeeds::Objects(objects) if objects.iter().any(|object| dweller.object == Some(*object)))
+                        && matches!(tn.into_inner(), TaskNeeds::Objects(objects) if objects.contains(&object))
                 });
 
             if not_working_on_task_that_needs_it {
@@ -807,28 +1068,27 @@ pub fn update_pickups(
         }
     }
 
-    let task_indexes = DashSet::new();
+    // One Pickup task per tile. Tasks are handled one at a time, each claim is made
+    // before the next search so that two tasks never claim the same units
+    let mut task_indexes = HashSet::new();
 
-    q_tasks.par_iter().for_each(|(task, task_needs)| {
-        // Closure result enum
-        enum TryFindObjectResult {
-            Wait,
-            Found,
-            NotFound,
-        }
+    // Oldest tasks first, the new Pickup tasks and their ids come in the same order every run
+    let mut tasks = q_tasks.iter().collect::<Vec<_>>();
+    tasks.sort_by_key(|(task, _)| task.id);
 
-        if task.dweller.is_some()
-            || matches!(
-                task.kind,
-                TaskKind::Stockpile | TaskKind::Workstation { amount: 0 }
-            )
-        {
-            return;
+    for (task, task_needs) in tasks {
+        if task.dweller.is_some() || matches!(task.kind, TaskKind::Stockpile) {
+            continue;
         }
 
         if let TaskNeeds::Objects(needs_objects) = task_needs.into_inner() {
+            // Fetching ingredients is part of the work of the task needing them
+            let category = tilemap_data
+                .get(task.pos)
+                .and_then(|tile| WorkCategory::for_task(task.kind, tile));
+
             // Closure to find an object for a task
-            let try_find_object = |needs_object: &ObjectId| {
+            let mut try_find_object = |needs_object: &ObjectId, mut needed: usize| {
                 // check if it needs a new Pickup task:
                 // check for existing Pickup tasks for the required object
                 // or Dwellers with the required object
@@ -838,49 +1098,55 @@ pub fn update_pickups(
                     return TryFindObjectResult::Wait;
                 }
 
-                // Find object: iter on stockpiles tasks containing required object, sort by distance
-                let stockpiles = q_tasks
-                    .iter()
-                    .filter_map(|(t, _)| {
-                        if matches!(t.kind, TaskKind::Stockpile)
-                            && !task_indexes.contains(&t.pos)
-                            && matches!(
-                                tilemap_data.get(t.pos),
-                                Some(TilePlaced {
-                                    object: Some(o),
-                                    ..
-                                }) if *needs_object == o
-                            )
-                        {
-                            const CHUNK_SIZE_SQUARED: i32 = (CHUNK_SIZE * CHUNK_SIZE) as i32;
-                            let distance = t.pos.distance_squared(task.pos);
-                            if distance < CHUNK_SIZE_SQUARED {
-                                return Some((t.into_inner(), distance));
-                            }
-                        }
-
-                        None
-                    })
-                    .collect::<Vec<_>>();
-
-                // Get closest stockpile
-                let stockpile = stockpiles.into_iter().min_by_key(|(_, distance)| *distance);
+                let mut result = TryFindObjectResult::NotFound;
+
+                // Find objects: nearest stacks, stored in a zone or lying around,
+                // until they cover the needed units
+                while needed > 0 {
+                    let Some((pos, tile)) =
+                        TilemapData::find_from_center_chunk_size(task.pos, |pos| {
+                            !task_indexes.contains(&pos)
+                                && tilemap_data.get(pos).is_some_and(|tile| {
+                                    tile.object == Some(*needs_object)
+                                        && is_haulable(*needs_object)
+                                        && TaskKind::Pickup.is_valid_on_tile(tile)
+                                        && reservations.available(pos, tile) > 0
+                                })
+                        })
+                        .and_then(|pos| Some((pos, tilemap_data.get(pos)?)))
+                    else {
+                        break;
+                    };
 
-                if let Some((Task { pos, .. }, _)) = stockpile {
                     debug!("Found object {needs_object:?} at {pos:?} for {task:?}");
 
-                    par_commands.command_scope(|mut commands| {
-                        commands.spawn(TaskBundle::new(
-                            Task::new(*pos, TaskKind::Pickup, None, &tilemap_data),
+                    let pickup = commands
+                        .spawn(TaskBundle::new(
+                            Task::new(pos, TaskKind::Pickup, None, &tilemap_data)
+                                .with_category(category),
                             TaskNeeds::EmptyHands,
-                        ));
-                    });
+                        ))
+                        .id();
+
+                    let count = reservations
+                        .available(pos, tile)
+                        .min(Inventory::slot_capacity(*needs_object));
 
                     task_indexes.insert(pos);
-                    return TryFindObjectResult::Found;
+                    reservations.reserve(
+                        pos,
+                        Reservation {
+                            object: *needs_object,
+                            count,
+                            task: pickup,
+                            dweller: None,
+                        },
+                    );
+                    needed = needed.saturating_sub(usize::from(count));
+                    result = TryFindObjectResult::Found;
                 }
 
-                TryFindObjectResult::NotFound
+                result
             };
 
             match task.kind {
@@ -888,17 +1154,28 @@ pub fn update_pickups(
                     result: BuildResult::Object(object),
                 } => {
                     // for Build tasks, check if the goal object is directly available
-                    match try_find_object(&object) {
-                        TryFindObjectResult::Found | TryFindObjectResult::Wait => return,
+                    match try_find_object(&object, 1) {
+                        TryFindObjectResult::Found | TryFindObjectResult::Wait => continue,
                         TryFindObjectResult::NotFound => {}
                     }
                 }
                 _ => {}
             }
 
+            // Each object once, a stack can cover several units
+            let mut searched = Vec::new();
             for needs_object in needs_objects {
-                try_find_object(needs_object);
+                if searched.contains(needs_object) {
+                    continue;
+                }
+                searched.push(*needs_object);
+
+                let needed = needs_objects
+                    .iter()
+                    .filter(|object| *object == needs_object)
+                    .count();
+                try_find_object(needs_object, needed);
             }
         }
-    });
+    }
 }
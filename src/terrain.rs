@@ -5,11 +5,13 @@ use rand::{rngs::StdRng, Rng, SeedableRng};
 use crate::{
     data::{ObjectId, StructureId, TileId},
     init_tilemap,
+    simulation::{SimulationRng, WorldSeed},
     tasks::{Task, TaskBundle, TaskKind, TaskNeeds},
     tilemap_data::TilemapData,
-    tiles::TilePlaced,
+    tiles::{LegacyQualityTilePlaced, LegacyTilePlaced, TilePlaced},
     utils::write_to_file,
-    MobBundle, SaveName, SpawnDwellersOnChunk, SpawnMobsOnChunk, CHUNK_SIZE, SAVE_DIR,
+    MobBundle, MobSpawnTrigger, SaveName, SpawnDwellersOnChunk, SpawnMobsOnChunk, CHUNK_SIZE,
+    SAVE_DIR,
 };
 
 const MOBS_SCALE: f64 = 0.1;
@@ -61,10 +63,10 @@ pub fn load_chunks(
     mut ev_unload: EventReader<UnloadChunk>,
     mut tilemap_data: ResMut<TilemapData>,
     save_name: Res<SaveName>,
+    world_seed: Res<WorldSeed>,
     mut ev_spawn_mobs: EventWriter<SpawnMobsOnChunk>,
 ) {
-    // Seed is based on the save name
-    let seed = save_name.0.as_bytes().iter().map(|b| *b as u32).sum();
+    let seed = world_seed.0;
     let noise_mountains = RidgedMulti::<Perlin>::new(seed);
     let noise_climate = Simplex::new(seed);
     let noise_structures = Simplex::new(seed + 1);
@@ -93,8 +95,20 @@ pub fn load_chunks(
             chunk_index.x, chunk_index.y
         ))
         .ok()
-        .and_then(|data| bitcode::decode::<Vec<TilePlaced>>(&data).ok())
-        {
+        .and_then(|data| {
+            bitcode::decode::<Vec<TilePlaced>>(&data)
+                .ok()
+                .or_else(|| {
+                    bitcode::decode::<Vec<LegacyQualityTilePlaced>>(&data)
+                        .ok()
+                        .map(|tiles| tiles.into_iter().map(TilePlaced::from).collect())
+                })
+                .or_else(|| {
+                    bitcode::decode::<Vec<LegacyTilePlaced>>(&data)
+                        .ok()
+                        .map(|tiles| tiles.into_iter().map(TilePlaced::from).collect())
+                })
+        }) {
             debug!("Loading chunk {} from save file", chunk_index);
 
             // Load in TilemapData
@@ -110,7 +124,7 @@ pub fn load_chunks(
                 chunk_index.y as f64 * MOBS_SCALE,
             ]) > MOBS_THRESHOLD
             {
-                ev_spawn_mobs.send(SpawnMobsOnChunk(*chunk_index));
+                ev_spawn_mobs.send(SpawnMobsOnChunk(*chunk_index, MobSpawnTrigger::Generated));
             }
 
             // Generate tiles
@@ -292,6 +306,7 @@ pub fn load_chunks(
 pub fn update_terrain(
     mut commands: Commands,
     mut tilemap_data: ResMut<TilemapData>,
+    mut rng: ResMut<SimulationRng>,
     q_tasks: Query<&Task>,
 ) {
     let mut to_set = vec![]; //because cant modify tilemap_data while iterating
@@ -308,8 +323,6 @@ pub fn update_terrain(
                     if let Some(object) = tile.object {
                         match object {
                             ObjectId::Farm => {
-                                let mut rng = rand::rng();
-
                                 if rng.random_bool(0.01) {
                                     to_set.push((index, tile.id.with(ObjectId::WheatPlant)));
                                 }
@@ -1,4 +1,4 @@
-use bevy::{prelude::*, utils::HashMap};
+use bevy::{prelude::*, sprite::Anchor, utils::HashMap};
 use bevy_ecs_tilemap::{
     map::{
         TilemapGridSize, TilemapId, TilemapRenderSettings, TilemapSize, TilemapTexture,
@@ -8,7 +8,7 @@ use bevy_ecs_tilemap::{
     TilemapBundle,
 };
 
-use crate::{tilemap_data::TilemapData, ObjectData, TileData, TilePlaced};
+use crate::{game_time::GameTime, tilemap_data::TilemapData, ObjectData, TileData, TilePlaced};
 
 pub const TILE_SIZE_U: u32 = 16;
 pub const TILE_SIZE: f32 = TILE_SIZE_U as f32;
@@ -21,6 +21,16 @@ pub struct ChunkTileLayer;
 #[derive(Component)]
 pub struct ChunkObjectLayer;
 
+/// Night tint over the tiles and objects of a chunk, child of the object layer
+#[derive(Component)]
+pub struct DarknessOverlay;
+
+/// Number of units of the stack on the tile at this index, child of the object layer
+#[derive(Component)]
+pub struct StackCountLabel(IVec2);
+
+/// # Panics
+/// If the sprites in `assets/tiles` cannot be listed
 pub fn init_tilemap(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.insert_resource(TilemapData::default());
 
@@ -50,6 +60,7 @@ pub fn manage_chunks(
     >,
     mut tilemap_data: ResMut<TilemapData>,
     tilemap_textures: Res<TilemapTextures>,
+    game_time: Res<GameTime>,
 ) {
     let mut created_chunks = Vec::new();
 
@@ -82,10 +93,21 @@ pub fn manage_chunks(
         ));
 
         // Object layer
-        commands.spawn((
-            ChunkObjectLayer,
-            new_tilemap(tilemap_textures.textures.clone(), pos.extend(1.0)),
-        ));
+        commands
+            .spawn((
+                ChunkObjectLayer,
+                new_tilemap(tilemap_textures.textures.clone(), pos.extend(1.0)),
+            ))
+            .with_child((
+                DarknessOverlay,
+                Sprite {
+                    color: Color::BLACK.with_alpha(game_time.darkness()),
+                    custom_size: Some(Vec2::splat(CHUNK_SIZE as f32 * TILE_SIZE)),
+                    anchor: Anchor::BottomLeft,
+                    ..default()
+                },
+                Transform::from_xyz(-TILE_SIZE / 2.0, -TILE_SIZE / 2.0, 0.5),
+            ));
 
         created_chunks.push(chunk_index);
     }
@@ -109,6 +131,18 @@ pub fn manage_chunks(
     }
 }
 
+/// Darken the chunks as the night comes
+pub fn update_darkness(
+    game_time: Res<GameTime>,
+    mut q_overlays: Query<&mut Sprite, With<DarknessOverlay>>,
+) {
+    let darkness = game_time.darkness();
+
+    for mut sprite in &mut q_overlays {
+        sprite.color.set_alpha(darkness);
+    }
+}
+
 fn new_tilemap(texture: TilemapTexture, pos: Vec3) -> TilemapBundle {
     TilemapBundle {
         grid_size: TilemapGridSize::new(TILE_SIZE, TILE_SIZE),
@@ -140,11 +174,21 @@ pub fn update_tilemap_from_data(
         (Entity, &mut TileStorage, &Transform),
         (With<ChunkObjectLayer>, Without<ChunkTileLayer>),
     >,
+    mut q_labels: Query<(Entity, &StackCountLabel, &mut Text2d)>,
     mut tilemap_data: ResMut<TilemapData>,
     mut tilemap_textures: ResMut<TilemapTextures>,
 ) {
     let tiles_to_update = tilemap_data.tiles_to_update.drain().collect::<Vec<_>>();
 
+    if tiles_to_update.is_empty() {
+        return;
+    }
+
+    let labels = q_labels
+        .iter()
+        .map(|(entity, label, _)| (label.0, entity))
+        .collect::<HashMap<_, _>>();
+
     for (index, tile) in tiles_to_update {
         let chunk_index = TilemapData::index_to_chunk(index).0;
         let tile_pos = TilePos {
@@ -226,6 +270,40 @@ pub fn update_tilemap_from_data(
             commands.entity(object_entity).despawn_recursive();
             object_layer_chunk_storage.remove(&tile_pos);
         }
+
+        // add, update or remove the stack count and quality
+        let label = labels.get(&index).copied();
+
+        if tile.object.is_some() && (tile.count > 1 || tile.quality > 0) {
+            let label_text = match (tile.count, tile.quality) {
+                (count, 0) => count.to_string(),
+                (1, quality) => format!("Q{quality}"),
+                (count, quality) => format!("{count} Q{quality}"),
+            };
+
+            if let Some((_, _, mut text)) = label.and_then(|entity| q_labels.get_mut(entity).ok()) {
+                text.0 = label_text;
+            } else {
+                let label_entity = commands
+                    .spawn((
+                        StackCountLabel(index),
+                        Text2d::new(label_text),
+                        Anchor::BottomRight,
+                        // The layer is centered on its first tile
+                        Transform::from_xyz(
+                            (tile_pos.x as f32 + 0.5) * TILE_SIZE - 1.,
+                            (tile_pos.y as f32 - 0.5) * TILE_SIZE,
+                            1.0,
+                        )
+                        .with_scale(Vec3::splat(0.25)),
+                    ))
+                    .id();
+
+                commands.entity(object_layer_entity).add_child(label_entity);
+            }
+        } else if let Some(label_entity) = label {
+            commands.entity(label_entity).despawn_recursive();
+        }
     }
 }
 
@@ -236,6 +314,7 @@ pub struct TilemapTextures {
 }
 
 impl TilemapTextures {
+    #[must_use]
     pub fn new(textures: TilemapTexture) -> Self {
         Self {
             textures,
@@ -243,12 +322,12 @@ impl TilemapTextures {
         }
     }
 
-    pub fn get_atlas_index_tile(&mut self, tile: TileData) -> TileTextureIndex {
+    pub fn get_atlas_index_tile(&mut self, tile: &'static TileData) -> TileTextureIndex {
         let folder = if tile.is_wall() { "walls" } else { "floors" };
         self.get_atlas_index(folder, tile.filename())
     }
 
-    pub fn get_atlas_index_object(&mut self, object: ObjectData) -> TileTextureIndex {
+    pub fn get_atlas_index_object(&mut self, object: &'static ObjectData) -> TileTextureIndex {
         self.get_atlas_index("objects", object.filename())
     }
 
@@ -1,16 +1,35 @@
-use bevy::{prelude::*, utils::HashMap};
+use std::sync::Arc;
 
-use crate::{utils::div_to_floor, TilePlaced, CHUNK_SIZE};
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use pathfinding::directed::astar::astar;
+
+use crate::{
+    data::ObjectId,
+    tilemap_pathfinding::{pathfinding_state, PathfindingGraph},
+    tilemap_regions::ConnectivityRegions,
+    utils::div_to_floor,
+    TilePlaced, CHUNK_SIZE, DEFAULT_MOVEMENT_COST, MIN_MOVEMENT_COST,
+};
 
 #[derive(Resource, Default)]
 pub struct TilemapData {
-    pub chunks: HashMap<IVec2, Vec<Option<TilePlaced>>>,
+    pub chunks: HashMap<IVec2, Arc<Vec<Option<TilePlaced>>>>,
     pub tiles_to_update: HashMap<IVec2, TilePlaced>,
     pub chunks_to_remove: Vec<IVec2>,
+    /// Tiles holding each object, per chunk
+    objects_count: HashMap<IVec2, HashMap<ObjectId, u32>>,
+    /// Tiles holding a carriable object, per chunk
+    carriable_objects: HashMap<IVec2, HashSet<IVec2>>,
+    pathfinding_graph: PathfindingGraph,
+    regions: ConnectivityRegions,
 }
 
 impl TilemapData {
     #[inline]
+    #[must_use]
     pub fn index_to_chunk(index: IVec2) -> (IVec2, usize) {
         let isize = IVec2::splat(CHUNK_SIZE as i32);
         let c = div_to_floor(index, isize);
@@ -19,6 +38,7 @@ impl TilemapData {
     }
 
     #[inline]
+    #[must_use]
     pub fn chunk_to_index(chunk_index: IVec2, local_index: usize) -> IVec2 {
         chunk_index * CHUNK_SIZE as i32
             + IVec2::new(
@@ -28,15 +48,55 @@ impl TilemapData {
     }
 
     pub fn set(&mut self, index: IVec2, tile: TilePlaced) {
+        let previous = self.get(index);
+
+        if previous.map(pathfinding_state) != Some(pathfinding_state(tile)) {
+            self.pathfinding_graph.mark_dirty(index);
+            self.regions.mark_chunk_dirty(Self::index_to_chunk(index).0);
+        }
+
+        let previous_object = previous.and_then(|previous| previous.object);
+        if previous_object != tile.object {
+            let counts = self
+                .objects_count
+                .entry(Self::index_to_chunk(index).0)
+                .or_default();
+
+            if let Some(count) = previous_object.and_then(|object| counts.get_mut(&object)) {
+                *count = count.saturating_sub(1);
+            }
+
+            if let Some(object) = tile.object {
+                *counts.entry(object).or_default() += 1;
+            }
+
+            let carriable = self
+                .carriable_objects
+                .entry(Self::index_to_chunk(index).0)
+                .or_default();
+            if tile
+                .object
+                .is_some_and(|object| object.data().is_carriable())
+            {
+                carriable.insert(index);
+            } else {
+                carriable.remove(&index);
+            }
+        }
+
         self.tiles_to_update.insert(index, tile);
         self.tiles_to_update.extend(self.neighbours(index)); // necessary for lighting
 
+        // Chunks are shared with pathfinding snapshots, copy on write
         let idx = Self::index_to_chunk(index);
-        self.chunks
-            .entry(idx.0)
-            .or_insert_with(|| vec![None; (CHUNK_SIZE * CHUNK_SIZE) as usize])[idx.1] = Some(tile);
+        Arc::make_mut(
+            self.chunks
+                .entry(idx.0)
+                .or_insert_with(|| Arc::new(vec![None; (CHUNK_SIZE * CHUNK_SIZE) as usize])),
+        )[idx.1] = Some(tile);
     }
 
+    #[must_use]
     pub fn get(&self, index: IVec2) -> Option<TilePlaced> {
         let idx = Self::index_to_chunk(index);
         self.chunks
@@ -47,6 +107,25 @@ impl TilemapData {
     }
 
     pub fn set_chunk(&mut self, chunk_index: IVec2, chunk_data: Vec<TilePlaced>) {
+        let mut counts = HashMap::<ObjectId, u32>::new();
+        for object in chunk_data.iter().filter_map(|tile| tile.object) {
+            *counts.entry(object).or_default() += 1;
+        }
+        self.objects_count.insert(chunk_index, counts);
+
+        self.carriable_objects.insert(
+            chunk_index,
+            chunk_data
+                .iter()
+                .enumerate()
+                .filter(|(_, tile)| {
+                    tile.object
+                        .is_some_and(|object| object.data().is_carriable())
+                })
+                .map(|(i, _)| Self::chunk_to_index(chunk_index, i))
+                .collect(),
+        );
+
         self.tiles_to_update.extend(
             chunk_data
                 .iter()
@@ -54,19 +133,133 @@ impl TilemapData {
                 .map(|(i, tile)| (Self::chunk_to_index(chunk_index, i), *tile)),
         );
 
-        self.chunks
-            .insert(chunk_index, chunk_data.into_iter().map(Some).collect());
+        self.chunks.insert(
+            chunk_index,
+            Arc::new(chunk_data.into_iter().map(Some).collect()),
+        );
+
+        self.pathfinding_graph.mark_chunk_dirty(chunk_index);
+        self.regions.mark_chunk_dirty(chunk_index);
     }
 
-    pub fn remove_chunk(&mut self, index: IVec2) -> Option<Vec<Option<TilePlaced>>> {
+    pub fn remove_chunk(&mut self, index: IVec2) -> Option<Arc<Vec<Option<TilePlaced>>>> {
         self.chunks_to_remove.push(index);
+        self.objects_count.remove(&index);
+        self.carriable_objects.remove(&index);
+        self.pathfinding_graph.mark_chunk_dirty(index);
+        self.regions.remove_chunk(index);
         self.chunks.remove(&index)
     }
 
+    /// Tiles of the chunk holding `object`
+    #[must_use]
+    pub fn objects_in_chunk(&self, chunk_index: IVec2, object: ObjectId) -> u32 {
+        self.objects_count
+            .get(&chunk_index)
+            .and_then(|counts| counts.get(&object))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Positions of the tiles holding a carriable object, in no particular order
+    pub fn carriable_objects(&self) -> impl Iterator<Item = IVec2> + '_ {
+        self.carriable_objects.values().flatten().copied()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn are_pathfinding_caches_dirty(&self) -> bool {
+        self.pathfinding_graph.is_dirty() || self.regions.is_dirty()
+    }
+
+    pub fn rebuild_pathfinding_caches(&mut self) {
+        if self.pathfinding_graph.is_dirty() {
+            let mut pathfinding_graph = std::mem::take(&mut self.pathfinding_graph);
+            pathfinding_graph.rebuild(self);
+            self.pathfinding_graph = pathfinding_graph;
+        }
+
+        if self.regions.is_dirty() {
+            let mut regions = std::mem::take(&mut self.regions);
+            regions.rebuild(self);
+            self.regions = regions;
+        }
+    }
+
+    /// Cheap copy of the loaded chunks and the pathfinding graph, to find paths off the main thread.
+    /// Connectivity regions are not included: check `may_be_connected` before
+    #[must_use]
+    pub fn snapshot(&self) -> Self {
+        Self {
+            chunks: self.chunks.clone(),
+            pathfinding_graph: self.pathfinding_graph.clone(),
+            ..default()
+        }
+    }
+
+    /// False if both positions are known to be in different connected regions, so no path exists
+    #[must_use]
+    pub fn may_be_connected(&self, a: IVec2, b: IVec2) -> bool {
+        if self.regions.is_dirty() {
+            return true;
+        }
+
+        match (self.regions.region(a), self.regions.region(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        }
+    }
+
+    /// Path from `from` to `to`, listed from `from` to `to`, with its cost
+    #[must_use]
+    pub fn find_path(&self, from: IVec2, to: IVec2) -> Option<(Vec<IVec2>, i32)> {
+        // Use the hierarchical graph for paths leaving the chunk
+        if Self::index_to_chunk(from).0 != Self::index_to_chunk(to).0
+            && !self.pathfinding_graph.is_dirty()
+        {
+            if let Some(path) = self.pathfinding_graph.find_path(from, to, self) {
+                return Some(path);
+            }
+        }
+
+        self.find_path_within(from, to, i32::MAX)
+    }
+
+    /// Path from `from` to `to` never going further than `radius` tiles from `from`, listed from `from` to `to`, with its cost.
+    /// The search cannot spread further, which keeps it cheap when `to` is near
+    #[must_use]
+    pub fn find_path_within(
+        &self,
+        from: IVec2,
+        to: IVec2,
+        radius: i32,
+    ) -> Option<(Vec<IVec2>, i32)> {
+        astar(
+            &from,
+            |p| {
+                self.non_blocking_neighbours_pos(*p, true)
+                    .into_iter()
+                    .filter(|p| (*p - from).abs().max_element() <= radius)
+                    .map(|p| (p, self.movement_cost(p) as i32))
+            },
+            |p| (p.x - to.x).abs().max((p.y - to.y).abs()) * MIN_MOVEMENT_COST as i32,
+            |p| *p == to,
+        )
+    }
+
+    /// Cost of walking onto a tile
+    #[inline]
+    pub fn movement_cost(&self, pos: IVec2) -> u32 {
+        self.get(pos)
+            .map_or(DEFAULT_MOVEMENT_COST, TilePlaced::movement_cost)
+    }
+
+    #[must_use]
     pub fn local_index_to_global(chunk_index: IVec2, local_index: IVec2) -> IVec2 {
         chunk_index * CHUNK_SIZE as i32 + local_index
     }
 
+    #[must_use]
     pub fn neighbours(&self, pos: IVec2) -> Vec<(IVec2, TilePlaced)> {
         [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y]
             .into_iter()
@@ -82,6 +275,7 @@ impl TilemapData {
             .collect()
     }
 
+    #[must_use]
     pub fn non_blocking_neighbours_pos(&self, pos: IVec2, diagonal: bool) -> Vec<IVec2> {
         let mut result: Vec<IVec2> = self
             .neighbours(pos)
@@ -126,6 +320,20 @@ impl TilemapData {
         result
     }
 
+    /// Walls, or doors set in them, are found within `radius` tiles in the 4 directions
+    #[must_use]
+    pub fn is_walled_in(&self, pos: IVec2, radius: i32) -> bool {
+        [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y]
+            .into_iter()
+            .all(|direction| {
+                (1..=radius).any(|i| {
+                    self.get(pos + direction * i).is_some_and(|tile| {
+                        tile.id.data().is_wall() || tile.object == Some(ObjectId::Door)
+                    })
+                })
+            })
+    }
+
     pub fn find_from_center_chunk_size(
         center: IVec2,
         is_valid: impl Fn(IVec2) -> bool,
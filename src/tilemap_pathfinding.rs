@@ -0,0 +1,334 @@
+use std::sync::Arc;
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use pathfinding::directed::{
+    astar::astar,
+    dijkstra::{build_path, dijkstra_all},
+};
+
+use crate::{tilemap_data::TilemapData, TilePlaced, CHUNK_SIZE, MIN_MOVEMENT_COST};
+
+/// Border openings at least this long get a transition at both ends instead of one in the middle
+const LONG_OPENING_LENGTH: i32 = 6;
+
+/// Hierarchical pathfinding (HPA*) graph.
+/// Chunks are clusters, linked by transitions on the openings of their borders.
+/// Paths between the entrances of a cluster are cached and rebuilt only when the cluster changes.
+#[derive(Default, Clone)]
+pub struct PathfindingGraph {
+    /// Transitions between a chunk and its +X or +Y neighbour, keyed by (chunk, neighbour)
+    borders: HashMap<(IVec2, IVec2), Vec<(IVec2, IVec2)>>,
+    /// Shared with pathfinding snapshots
+    clusters: HashMap<IVec2, Arc<Cluster>>,
+    dirty_chunks: HashSet<IVec2>,
+}
+
+#[derive(Default)]
+struct Cluster {
+    /// Entrance -> paths to the other entrances of the cluster with their cost, staying inside the cluster
+    paths: HashMap<IVec2, Vec<(IVec2, Vec<IVec2>, u32)>>,
+    /// Entrance -> entrances of neighbouring clusters, one step away
+    transitions: HashMap<IVec2, Vec<IVec2>>,
+}
+
+#[inline]
+fn chunk_of(pos: IVec2) -> IVec2 {
+    TilemapData::index_to_chunk(pos).0
+}
+
+#[inline]
+fn border_key(chunk: IVec2, neighbour: IVec2) -> (IVec2, IVec2) {
+    if chunk.x < neighbour.x || chunk.y < neighbour.y {
+        (chunk, neighbour)
+    } else {
+        (neighbour, chunk)
+    }
+}
+
+/// Only these tile properties matter for `TilemapData::non_blocking_neighbours_pos` and path costs
+#[inline]
+pub fn pathfinding_state(tile: TilePlaced) -> (bool, bool, u32) {
+    (
+        tile.is_blocking(),
+        tile.id.data().is_wall(),
+        tile.movement_cost(),
+    )
+}
+
+fn cluster_successors(
+    pos: IVec2,
+    chunk: IVec2,
+    tilemap_data: &TilemapData,
+) -> impl Iterator<Item = (IVec2, u32)> + '_ {
+    tilemap_data
+        .non_blocking_neighbours_pos(pos, true)
+        .into_iter()
+        .filter(move |p| chunk_of(*p) == chunk)
+        .map(|p| (p, tilemap_data.movement_cost(p)))
+}
+
+impl PathfindingGraph {
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty_chunks.is_empty()
+    }
+
+    /// A tile changed: its cluster must be rebuilt, and so do the neighbouring clusters if it is on a border
+    pub fn mark_dirty(&mut self, pos: IVec2) {
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                self.dirty_chunks.insert(chunk_of(pos + IVec2::new(dx, dy)));
+            }
+        }
+    }
+
+    /// A chunk was loaded or unloaded
+    pub fn mark_chunk_dirty(&mut self, chunk_index: IVec2) {
+        self.dirty_chunks.insert(chunk_index);
+
+        for direction in [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y] {
+            self.dirty_chunks.insert(chunk_index + direction);
+        }
+    }
+
+    pub fn rebuild(&mut self, tilemap_data: &TilemapData) {
+        let dirty_chunks = std::mem::take(&mut self.dirty_chunks);
+        let mut clusters_to_rebuild = HashSet::new();
+
+        for chunk_index in dirty_chunks {
+            clusters_to_rebuild.insert(chunk_index);
+
+            for direction in [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y] {
+                let neighbour = chunk_index + direction;
+                let key = border_key(chunk_index, neighbour);
+                let transitions = Self::compute_transitions(key, tilemap_data);
+
+                let changed = self
+                    .borders
+                    .get(&key)
+                    .map_or(!transitions.is_empty(), |old| *old != transitions);
+
+                if changed {
+                    clusters_to_rebuild.insert(neighbour);
+                }
+
+                if transitions.is_empty() {
+                    self.borders.remove(&key);
+                } else {
+                    self.borders.insert(key, transitions);
+                }
+            }
+        }
+
+        for chunk_index in clusters_to_rebuild {
+            if tilemap_data.chunks.contains_key(&chunk_index) {
+                let cluster = self.build_cluster(chunk_index, tilemap_data);
+                self.clusters.insert(chunk_index, Arc::new(cluster));
+            } else {
+                self.clusters.remove(&chunk_index);
+            }
+        }
+    }
+
+    fn compute_transitions(
+        (chunk, neighbour): (IVec2, IVec2),
+        tilemap_data: &TilemapData,
+    ) -> Vec<(IVec2, IVec2)> {
+        let direction = neighbour - chunk;
+        let along = IVec2::new(direction.y, direction.x);
+        let last = CHUNK_SIZE as i32 - 1;
+        let origin = TilemapData::local_index_to_global(chunk, direction * last);
+
+        let is_open = |i: i32| {
+            let pos = origin + along * i;
+
+            tilemap_data.get(pos).is_some_and(|t| !t.is_blocking())
+                && tilemap_data
+                    .get(pos + direction)
+                    .is_some_and(|t| !t.is_blocking())
+        };
+
+        let mut transitions = vec![];
+        let mut i = 0;
+
+        while i <= last {
+            if !is_open(i) {
+                i += 1;
+                continue;
+            }
+
+            let start = i;
+            while i < last && is_open(i + 1) {
+                i += 1;
+            }
+            let end = i;
+
+            let openings = if end - start + 1 >= LONG_OPENING_LENGTH {
+                vec![start, end]
+            } else {
+                vec![i32::midpoint(start, end)]
+            };
+
+            for opening in openings {
+                let pos = origin + along * opening;
+                transitions.push((pos, pos + direction));
+            }
+
+            i += 1;
+        }
+
+        transitions
+    }
+
+    fn build_cluster(&self, chunk_index: IVec2, tilemap_data: &TilemapData) -> Cluster {
+        let mut cluster = Cluster::default();
+
+        for direction in [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y] {
+            let key = border_key(chunk_index, chunk_index + direction);
+
+            for (a, b) in self.borders.get(&key).into_iter().flatten() {
+                let (entrance, other) = if chunk_of(*a) == chunk_index {
+                    (*a, *b)
+                } else {
+                    (*b, *a)
+                };
+
+                cluster.transitions.entry(entrance).or_default().push(other);
+            }
+        }
+
+        let entrances = cluster.transitions.keys().copied().collect::<Vec<_>>();
+
+        for entrance in &entrances {
+            let parents = dijkstra_all(entrance, |p| {
+                cluster_successors(*p, chunk_index, tilemap_data)
+            });
+
+            let paths = entrances
+                .iter()
+                .filter(|other| *other != entrance && parents.contains_key(*other))
+                .map(|other| (*other, build_path(other, &parents), parents[other].1))
+                .collect();
+
+            cluster.paths.insert(*entrance, paths);
+        }
+
+        cluster
+    }
+
+    /// Path from `from` to `to` through the abstract graph, listed from `from` to `to`
+    pub fn find_path(
+        &self,
+        from: IVec2,
+        to: IVec2,
+        tilemap_data: &TilemapData,
+    ) -> Option<(Vec<IVec2>, i32)> {
+        let from_chunk = chunk_of(from);
+        let to_chunk = chunk_of(to);
+
+        let from_cluster = self.clusters.get(&from_chunk)?;
+        let to_cluster = self.clusters.get(&to_chunk)?;
+
+        // Steps into `to` are only valid if it is not blocking
+        if tilemap_data.get(to).is_none_or(TilePlaced::is_blocking) {
+            return None;
+        }
+
+        // Link `from` and `to` to the entrances of their clusters
+        let from_parents =
+            dijkstra_all(&from, |p| cluster_successors(*p, from_chunk, tilemap_data));
+        let to_parents = dijkstra_all(&to, |p| cluster_successors(*p, to_chunk, tilemap_data));
+
+        let from_entrances = from_cluster
+            .paths
+            .keys()
+            .filter_map(|entrance| {
+                from_parents
+                    .get(entrance)
+                    .map(|(_, cost)| (*entrance, *cost))
+            })
+            .collect::<Vec<_>>();
+
+        let (nodes, cost) = astar(
+            &from,
+            |node| {
+                let mut successors = vec![];
+
+                if *node == from {
+                    successors.extend(from_entrances.iter().copied());
+                }
+
+                if let Some(cluster) = self.clusters.get(&chunk_of(*node)) {
+                    if let Some(paths) = cluster.paths.get(node) {
+                        successors.extend(paths.iter().map(|(other, _, cost)| (*other, *cost)));
+                    }
+
+                    if let Some(transitions) = cluster.transitions.get(node) {
+                        successors.extend(
+                            transitions
+                                .iter()
+                                .map(|other| (*other, tilemap_data.movement_cost(*other))),
+                        );
+                    }
+                }
+
+                if to_cluster.paths.contains_key(node) {
+                    // `to_parents` costs were accumulated walking away from `to`
+                    if let Some((_, cost)) = to_parents.get(node) {
+                        successors.push((
+                            to,
+                            cost - tilemap_data.movement_cost(*node)
+                                + tilemap_data.movement_cost(to),
+                        ));
+                    }
+                }
+
+                successors
+            },
+            |p| (p.x - to.x).abs().max((p.y - to.y).abs()) as u32 * MIN_MOVEMENT_COST,
+            |p| *p == to,
+        )?;
+
+        // Refine the abstract path into tiles
+        let mut path = vec![from];
+
+        for pair in nodes.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+
+            let segment = if chunk_of(a) != chunk_of(b) {
+                vec![a, b]
+            } else if b == to {
+                let mut segment = build_path(&a, &to_parents);
+                segment.reverse();
+                segment
+            } else if a == from {
+                build_path(&b, &from_parents)
+            } else {
+                self.clusters
+                    .get(&chunk_of(a))?
+                    .paths
+                    .get(&a)?
+                    .iter()
+                    .find(|(other, _, _)| *other == b)?
+                    .1
+                    .clone()
+            };
+
+            path.extend(segment.into_iter().skip(1));
+        }
+
+        // Never return a path the tile rules would not allow (e.g. a stale cache)
+        if path.windows(2).any(|pair| {
+            !tilemap_data
+                .non_blocking_neighbours_pos(pair[0], true)
+                .contains(&pair[1])
+        }) {
+            return None;
+        }
+
+        Some((path, cost as i32))
+    }
+}
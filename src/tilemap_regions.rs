@@ -0,0 +1,163 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{tilemap_data::TilemapData, TilePlaced, CHUNK_SIZE};
+
+/// Connected regions of non blocking tiles, following `TilemapData::non_blocking_neighbours_pos`.
+/// Each chunk is flood-filled on its own, then chunk components are merged across borders.
+#[derive(Default)]
+pub struct ConnectivityRegions {
+    /// Local component of each tile of a chunk (0 for blocking tiles), and number of components
+    chunks: HashMap<IVec2, (Vec<u32>, u32)>,
+    /// Region of each (chunk, local component)
+    regions: HashMap<(IVec2, u32), u32>,
+    dirty_chunks: HashSet<IVec2>,
+    needs_merge: bool,
+}
+
+impl ConnectivityRegions {
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.needs_merge || !self.dirty_chunks.is_empty()
+    }
+
+    pub fn mark_chunk_dirty(&mut self, chunk_index: IVec2) {
+        self.dirty_chunks.insert(chunk_index);
+    }
+
+    pub fn region(&self, pos: IVec2) -> Option<u32> {
+        let (chunk_index, local_index) = TilemapData::index_to_chunk(pos);
+        let (labels, _) = self.chunks.get(&chunk_index)?;
+
+        match labels[local_index] {
+            0 => None,
+            label => self.regions.get(&(chunk_index, label)).copied(),
+        }
+    }
+
+    pub fn rebuild(&mut self, tilemap_data: &TilemapData) {
+        for chunk_index in std::mem::take(&mut self.dirty_chunks) {
+            if tilemap_data.chunks.contains_key(&chunk_index) {
+                self.chunks
+                    .insert(chunk_index, Self::label_chunk(chunk_index, tilemap_data));
+            } else {
+                self.chunks.remove(&chunk_index);
+            }
+        }
+
+        self.needs_merge = false;
+        self.merge(tilemap_data);
+    }
+
+    fn label_chunk(chunk_index: IVec2, tilemap_data: &TilemapData) -> (Vec<u32>, u32) {
+        let mut labels = vec![0; (CHUNK_SIZE * CHUNK_SIZE) as usize];
+        let mut count = 0;
+
+        for local_index in 0..labels.len() {
+            if labels[local_index] != 0 {
+                continue;
+            }
+
+            let pos = TilemapData::chunk_to_index(chunk_index, local_index);
+
+            if tilemap_data.get(pos).is_none_or(TilePlaced::is_blocking) {
+                continue;
+            }
+
+            count += 1;
+            labels[local_index] = count;
+
+            let mut stack = vec![pos];
+
+            while let Some(pos) = stack.pop() {
+                for neighbour in tilemap_data.non_blocking_neighbours_pos(pos, true) {
+                    let (neighbour_chunk, neighbour_local) = TilemapData::index_to_chunk(neighbour);
+
+                    if neighbour_chunk == chunk_index && labels[neighbour_local] == 0 {
+                        labels[neighbour_local] = count;
+                        stack.push(neighbour);
+                    }
+                }
+            }
+        }
+
+        (labels, count)
+    }
+
+    /// Merge chunk components linked across chunk borders, with a union-find
+    fn merge(&mut self, tilemap_data: &TilemapData) {
+        fn find(parents: &mut [usize], mut i: usize) -> usize {
+            while parents[i] != i {
+                parents[i] = parents[parents[i]];
+                i = parents[i];
+            }
+            i
+        }
+
+        let mut offsets = HashMap::new();
+        let mut total = 0;
+
+        for (chunk_index, (_, count)) in &self.chunks {
+            offsets.insert(*chunk_index, total);
+            total += *count as usize;
+        }
+
+        let mut parents = (0..total).collect::<Vec<_>>();
+        let last = CHUNK_SIZE as i32 - 1;
+
+        for (chunk_index, (labels, _)) in &self.chunks {
+            for (local_index, label) in labels.iter().enumerate() {
+                let local = IVec2::new(
+                    local_index as i32 % CHUNK_SIZE as i32,
+                    local_index as i32 / CHUNK_SIZE as i32,
+                );
+
+                if *label == 0 || (local.x > 0 && local.x < last && local.y > 0 && local.y < last) {
+                    continue;
+                }
+
+                let pos = TilemapData::chunk_to_index(*chunk_index, local_index);
+
+                for neighbour in tilemap_data.non_blocking_neighbours_pos(pos, true) {
+                    let (neighbour_chunk, neighbour_local) = TilemapData::index_to_chunk(neighbour);
+
+                    if neighbour_chunk == *chunk_index {
+                        continue;
+                    }
+
+                    let Some((neighbour_labels, _)) = self.chunks.get(&neighbour_chunk) else {
+                        continue;
+                    };
+
+                    let neighbour_label = neighbour_labels[neighbour_local];
+
+                    if neighbour_label != 0 {
+                        let a = find(&mut parents, offsets[chunk_index] + *label as usize - 1);
+                        let b = find(
+                            &mut parents,
+                            offsets[&neighbour_chunk] + neighbour_label as usize - 1,
+                        );
+                        parents[a] = b;
+                    }
+                }
+            }
+        }
+
+        self.regions.clear();
+
+        for (chunk_index, (_, count)) in &self.chunks {
+            for label in 1..=*count {
+                let root = find(&mut parents, offsets[chunk_index] + label as usize - 1);
+                self.regions.insert((*chunk_index, label), root as u32);
+            }
+        }
+    }
+
+    pub fn remove_chunk(&mut self, chunk_index: IVec2) {
+        self.chunks.remove(&chunk_index);
+        self.dirty_chunks.remove(&chunk_index);
+        self.needs_merge = true;
+    }
+}
@@ -1,15 +1,34 @@
 use bevy::prelude::*;
 use bitcode::{Decode, Encode};
+use serde::Deserialize;
 
 use crate::data::{ObjectId, TileId};
 
-#[derive(Clone, Copy, Encode, Decode, Reflect, Default, Debug)]
+/// Movement cost of a regular floor, other costs are relative to it
+pub const DEFAULT_MOVEMENT_COST: u32 = 10;
+
+/// Lowest movement cost of any floor, keeps pathfinding heuristics admissible
+pub const MIN_MOVEMENT_COST: u32 = 7;
+
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, Reflect, Default, Deserialize, Debug)]
 pub struct TilePlaced {
     pub id: TileId,
+    #[serde(default)]
     pub object: Option<ObjectId>,
+    /// Units of the object stacked on the tile, ignored without an object
+    #[serde(default = "default_count")]
+    pub count: u8,
+    /// Quality of the stacked units, the lowest of them, set by the skill of whoever crafted them
+    #[serde(default)]
+    pub quality: u8,
+}
+
+fn default_count() -> u8 {
+    1
 }
 
 impl TilePlaced {
+    #[must_use]
     pub fn is_blocking(self) -> bool {
         self.id.data().is_wall()
             || self
@@ -17,39 +36,146 @@ impl TilePlaced {
                 .is_some_and(|o| ObjectId::data(&o).is_blocking())
     }
 
+    #[must_use]
     pub fn is_floor_free(self) -> bool {
         !self.is_blocking() && self.object.is_none()
     }
+
+    /// Cost of walking onto this tile, including the object on it
+    #[must_use]
+    pub fn movement_cost(self) -> u32 {
+        self.id.data().movement_cost()
+            + self
+                .object
+                .map_or(0, |o| ObjectId::data(&o).movement_cost())
+    }
+
+    /// Units of `object` that can still be stacked on this tile
+    #[must_use]
+    pub fn room_for(self, object: ObjectId) -> u8 {
+        if self.id.data().is_wall() {
+            return 0;
+        }
+
+        match self.object {
+            None => object.data().max_stack(),
+            Some(o) if o == object => object.data().max_stack().saturating_sub(self.count),
+            Some(_) => 0,
+        }
+    }
+
+    /// Stack `count` units of `object` with no quality, the tile must be empty or hold the same object
+    #[must_use]
+    pub fn add(self, object: ObjectId, count: u8) -> Self {
+        self.add_with_quality(object, count, 0)
+    }
+
+    /// Stack `count` units of `object` of a quality, the stack keeps the lowest quality
+    #[must_use]
+    pub fn add_with_quality(self, object: ObjectId, count: u8, quality: u8) -> Self {
+        if self.object == Some(object) {
+            Self {
+                count: self.count + count,
+                quality: self.quality.min(quality),
+                ..self
+            }
+        } else {
+            Self {
+                quality,
+                ..self.id.with_count(object, count)
+            }
+        }
+    }
+
+    /// Remove `count` units of the object, the tile is emptied when none are left
+    #[must_use]
+    pub fn take(self, count: u8) -> Self {
+        match self.object {
+            Some(_) if self.count > count => Self {
+                count: self.count - count,
+                ..self
+            },
+            _ => self.id.place(),
+        }
+    }
+
+    /// Speed multiplier when walking onto this tile
+    #[must_use]
+    pub fn speed_ratio(self) -> f32 {
+        DEFAULT_MOVEMENT_COST as f32 / self.movement_cost() as f32
+    }
 }
 
-pub struct TileData {
-    filename: &'static str,
-    wall: bool,
+/// Tile of the chunks saved before crafted objects had a quality
+#[derive(Decode)]
+pub struct LegacyTilePlaced {
+    id: TileId,
+    object: Option<ObjectId>,
 }
 
-impl TileData {
-    const fn new(filename: &'static str, wall: bool) -> Self {
-        Self { filename, wall }
+impl From<LegacyTilePlaced> for TilePlaced {
+    fn from(tile: LegacyTilePlaced) -> Self {
+        match tile.object {
+            Some(object) => tile.id.with(object),
+            None => tile.id.place(),
+        }
     }
+}
 
-    pub const fn floor(filename: &'static str) -> Self {
-        Self::new(filename, false)
-    }
+/// Tile of the chunks saved before objects were stacked
+#[derive(Decode)]
+pub struct LegacyQualityTilePlaced {
+    id: TileId,
+    object: Option<ObjectId>,
+    quality: u8,
+}
 
-    pub const fn wall(filename: &'static str) -> Self {
-        Self::new(filename, true)
+impl From<LegacyQualityTilePlaced> for TilePlaced {
+    fn from(tile: LegacyQualityTilePlaced) -> Self {
+        match tile.object {
+            Some(object) => TilePlaced {
+                quality: tile.quality,
+                ..tile.id.with(object)
+            },
+            None => tile.id.place(),
+        }
     }
+}
 
+#[derive(Deserialize)]
+pub struct TileData {
+    filename: String,
+    #[serde(default)]
+    wall: bool,
+    /// Must not be lower than `MIN_MOVEMENT_COST`
+    #[serde(default = "default_movement_cost")]
+    movement_cost: u32,
+}
+
+fn default_movement_cost() -> u32 {
+    DEFAULT_MOVEMENT_COST
+}
+
+impl TileData {
     #[inline]
+    #[must_use]
     pub fn is_wall(&self) -> bool {
         self.wall
     }
 
     #[inline]
-    pub fn filename(&self) -> &'static str {
-        self.filename
+    #[must_use]
+    pub fn movement_cost(&self) -> u32 {
+        self.movement_cost
     }
 
+    #[inline]
+    #[must_use]
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    #[must_use]
     pub fn sprite_path(&self) -> String {
         format!(
             "tiles/{}/{}.png",
@@ -60,28 +186,42 @@ impl TileData {
 }
 
 impl TileId {
+    #[must_use]
     pub fn with(self, object_id: ObjectId) -> TilePlaced {
+        self.with_count(object_id, 1)
+    }
+
+    #[must_use]
+    pub fn with_count(self, object_id: ObjectId, count: u8) -> TilePlaced {
         TilePlaced {
             id: self,
             object: Some(object_id),
+            count,
+            quality: 0,
         }
     }
 
+    #[must_use]
     pub fn place(self) -> TilePlaced {
         TilePlaced {
             id: self,
             object: None,
+            count: 1,
+            quality: 0,
         }
     }
 
+    #[must_use]
     pub fn s(self) -> Option<TilePlaced> {
         Some(self.place())
     }
 
+    #[must_use]
     pub fn i(self, object_id: ObjectId) -> Option<TilePlaced> {
         Some(self.with(object_id))
     }
 
+    #[must_use]
     pub fn is_transparent(self) -> bool {
         !self.data().is_wall() || self == Self::Water
     }
@@ -1,7 +1,10 @@
 use bevy::prelude::*;
 
 use crate::{
-    actions::CurrentAction, data::BUILD_RECIPES, extract_ok, utils::pascal_case_to_title_case,
+    actions::CurrentAction,
+    data::{ObjectId, DEFINITIONS},
+    extract_ok,
+    utils::pascal_case_to_title_case,
     ActionKind, Dweller, DwellersSelected, TaskKind, TaskNeeds, UiButton,
 };
 
@@ -33,13 +36,13 @@ pub fn spawn_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
                 ..default()
             })
             .with_children(|c| {
-                for (result, cost) in BUILD_RECIPES {
+                for (result, cost) in &DEFINITIONS.build_recipes {
                     c.spawn(UiButton)
                         .with_child(Text::new(pascal_case_to_title_case(&result.debug_name())))
                         .with_child(ImageNode::new(asset_server.load(result.sprite_path())))
                         .observe(get_observer_action_button(ActionKind::TaskWithNeeds(
                             TaskKind::Build { result: *result },
-                            TaskNeeds::Objects(cost.to_vec()),
+                            TaskNeeds::Objects(cost.clone()),
                         )));
                 }
             });
@@ -56,7 +59,6 @@ pub fn spawn_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
                     TaskKind::Harvest,
                     TaskKind::Hunt,
                     TaskKind::Pickup,
-                    TaskKind::Stockpile,
                     TaskKind::Smoothen,
                     TaskKind::Walk,
                 ] {
@@ -68,6 +70,20 @@ pub fn spawn_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
                         .observe(get_observer_action_button(ActionKind::Task(task_kind)));
                 }
 
+                c.spawn(UiButton)
+                    .with_child(Text::new("Stockpile"))
+                    .with_child(ImageNode::new(
+                        asset_server.load(TaskKind::Stockpile.sprite_path()),
+                    ))
+                    .observe(get_observer_action_button(ActionKind::StockpileZone));
+
+                c.spawn(UiButton)
+                    .with_child(Text::new("Graveyard"))
+                    .with_child(ImageNode::new(
+                        asset_server.load(ObjectId::Grave.data().sprite_path()),
+                    ))
+                    .observe(get_observer_action_button(ActionKind::GraveyardZone));
+
                 c.spawn(UiButton)
                     .with_child(Text::new("Cancel"))
                     .observe(get_observer_action_button(ActionKind::Cancel));
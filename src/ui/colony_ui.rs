@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+
+use crate::{extract_ok, game_time::GameTime, mood::ColonyMood, notifications::Notification};
+
+/// Seconds a notification stays on screen
+const NOTIFICATION_DURATION: f32 = 8.0;
+
+#[derive(Component)]
+pub struct NotificationsUi;
+
+#[derive(Component)]
+pub struct NotificationUi(Timer);
+
+#[derive(Component)]
+pub struct ColonyMoodUi;
+
+#[derive(Component)]
+pub struct GameTimeUi;
+
+/// Date and time, colony mood and notifications, in the top left corner
+pub fn spawn_colony_ui(mut commands: Commands) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(5.0),
+            ..default()
+        })
+        .with_children(|c| {
+            c.spawn((GameTimeUi, Text::new("")));
+            c.spawn((ColonyMoodUi, Text::new("")));
+
+            c.spawn((
+                NotificationsUi,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(5.0),
+                    ..default()
+                },
+            ));
+        });
+}
+
+pub fn update_colony_mood_ui(
+    mood: Res<ColonyMood>,
+    mut q_colony_mood_ui: Query<&mut Text, With<ColonyMoodUi>>,
+) {
+    if mood.is_changed() {
+        let mut text = extract_ok!(q_colony_mood_ui.get_single_mut());
+        text.0 = format!("Mood {}", mood.0);
+    }
+}
+
+pub fn update_game_time_ui(
+    game_time: Res<GameTime>,
+    mut q_game_time_ui: Query<&mut Text, With<GameTimeUi>>,
+) {
+    let mut text = extract_ok!(q_game_time_ui.get_single_mut());
+    let date = game_time.to_string();
+
+    // Only once a minute, so the text is not laid out again every frame
+    if text.0 != date {
+        text.0 = date;
+    }
+}
+
+pub fn update_notifications_ui(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ev_notification: EventReader<Notification>,
+    q_notifications_ui: Query<Entity, With<NotificationsUi>>,
+    mut q_notifications: Query<(Entity, &mut NotificationUi)>,
+) {
+    for (entity, mut notification) in &mut q_notifications {
+        if notification.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    let notifications_ui = extract_ok!(q_notifications_ui.get_single());
+
+    for Notification(message) in ev_notification.read() {
+        commands.entity(notifications_ui).with_child((
+            NotificationUi(Timer::from_seconds(NOTIFICATION_DURATION, TimerMode::Once)),
+            Text::new(message.clone()),
+            BackgroundColor(Color::BLACK.with_alpha(0.5)),
+        ));
+    }
+}
@@ -2,6 +2,12 @@ use bevy::prelude::*;
 
 mod actions_ui;
 pub use actions_ui::*;
+mod colony_ui;
+pub use colony_ui::*;
+mod stockpile_ui;
+pub use stockpile_ui::*;
+mod work_priorities_ui;
+pub use work_priorities_ui::*;
 mod workstation_ui;
 pub use workstation_ui::*;
 
@@ -0,0 +1,187 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    data::ObjectId, extract_ok, stockpiles::is_haulable, StockpileZone, UiBackground, BG_PRIMARY,
+    BG_TERTIARY,
+};
+
+#[derive(Event)]
+pub struct OpenStockpileZoneUi;
+
+#[derive(Component)]
+#[require(
+    Node(|| Node {
+        width: Val::Px(360.),
+        flex_direction: FlexDirection::Column,
+        row_gap: Val::Px(5.0),
+        padding: UiRect::all(Val::Px(5.0)),
+        border: UiRect::all(Val::Px(4.0)),
+        ..default()
+    }),
+    BorderColor(|| BorderColor(Color::BLACK)),
+    BackgroundColor(|| BackgroundColor(BG_PRIMARY))
+)]
+pub struct StockpileZoneUi(pub Entity, pub u128);
+
+pub fn observe_open_stockpile_zone_ui(
+    trigger: Trigger<OpenStockpileZoneUi>,
+    mut commands: Commands,
+    q_stockpile_zone_ui: Query<Entity, With<StockpileZoneUi>>,
+    q_zones: Query<&StockpileZone>,
+) {
+    for entity in &q_stockpile_zone_ui {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let entity = trigger.entity();
+    let zone = extract_ok!(q_zones.get(entity));
+
+    debug!("Stockpile zone UI opened: {:?}", zone);
+    commands
+        .spawn(UiBackground)
+        .observe(|trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+            commands.entity(trigger.entity()).despawn_recursive();
+        })
+        .with_child(StockpileZoneUi(
+            entity,
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos(),
+        ));
+}
+
+//TODO: use a better reactivity system
+pub fn update_stockpile_zone_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    q_stockpile_zone_ui: Query<(Entity, &StockpileZoneUi, &Parent)>,
+    q_zones: Query<&StockpileZone>,
+    mut changes: Local<HashMap<u128, StockpileZone>>,
+) {
+    for (ui_entity, stockpile_zone_ui, parent) in &q_stockpile_zone_ui {
+        let entity = stockpile_zone_ui.0;
+
+        // The zone was deleted, close the UI with its background
+        let Ok(zone) = q_zones.get(entity) else {
+            changes.remove(&stockpile_zone_ui.1);
+            commands.entity(parent.get()).despawn_recursive();
+            continue;
+        };
+
+        if changes.get(&stockpile_zone_ui.1) == Some(zone) {
+            continue;
+        }
+        changes.insert(stockpile_zone_ui.1, zone.clone());
+
+        let Some(mut ec) = commands.get_entity(ui_entity) else {
+            continue;
+        };
+
+        ec.despawn_descendants().with_children(|c| {
+            c.spawn(Node {
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                ..default()
+            })
+            .with_children(|c| {
+                c.spawn(Text::new(zone.name.clone()));
+                c.spawn(Text::new(format!("Priority {}", zone.priority)));
+
+                spawn_zone_button(c, entity, "-", |zone| {
+                    zone.priority -= 1;
+                });
+                spawn_zone_button(c, entity, "+", |zone| {
+                    zone.priority += 1;
+                });
+            });
+
+            // Allowed objects, click to toggle
+            c.spawn(Node {
+                flex_wrap: FlexWrap::Wrap,
+                justify_content: JustifyContent::Center,
+                column_gap: Val::Px(5.0),
+                row_gap: Val::Px(5.0),
+                ..default()
+            })
+            .with_children(|c| {
+                // Graveyards only take corpses, to bury them
+                for object in
+                    ObjectId::all().filter(|object| is_haulable(*object) && !zone.graveyard)
+                {
+                    let background = if zone.accepts(object) {
+                        BG_TERTIARY
+                    } else {
+                        BG_PRIMARY
+                    };
+
+                    c.spawn((
+                        Button,
+                        Node {
+                            border: UiRect::all(Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(background),
+                    ))
+                    .with_child(ImageNode::new(
+                        asset_server.load(object.data().sprite_path()),
+                    ))
+                    .observe(
+                        move |mut trigger: Trigger<Pointer<Click>>,
+                              mut q_zones: Query<&mut StockpileZone>| {
+                            trigger.propagate(false);
+                            let mut zone = extract_ok!(q_zones.get_mut(entity));
+                            zone.toggle(object);
+                        },
+                    );
+                }
+            });
+
+            c.spawn((
+                Button,
+                Node {
+                    padding: UiRect::all(Val::Px(5.0)),
+                    border: UiRect::all(Val::Px(4.0)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+            ))
+            .with_child((Text::new("Delete"), TextFont::from_font_size(20.0)))
+            .observe(
+                move |mut trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                    trigger.propagate(false);
+                    commands.entity(entity).despawn_recursive();
+                },
+            );
+        });
+    }
+}
+
+/// Button editing the zone `entity`
+fn spawn_zone_button(
+    c: &mut ChildBuilder,
+    entity: Entity,
+    label: &str,
+    edit: impl Fn(&mut StockpileZone) + Send + Sync + 'static,
+) {
+    c.spawn((
+        Button,
+        Node {
+            padding: UiRect::all(Val::Px(5.0)),
+            border: UiRect::all(Val::Px(4.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+    ))
+    .with_child((Text::new(label), TextFont::from_font_size(20.0)))
+    .observe(
+        move |mut trigger: Trigger<Pointer<Click>>, mut q_zones: Query<&mut StockpileZone>| {
+            trigger.propagate(false);
+            let mut zone = extract_ok!(q_zones.get_mut(entity));
+            edit(&mut zone);
+        },
+    );
+}
@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+
+use crate::{
+    dwellers::{Dweller, WorkCategory},
+    extract_ok,
+    hunting::HuntDistance,
+    HaulPriority, UiButton, UiWindow,
+};
+
+const NAME_WIDTH: f32 = 150.0;
+const CELL_WIDTH: f32 = 90.0;
+
+#[derive(Component)]
+pub struct WorkPrioritiesUi;
+
+#[derive(Component)]
+pub struct WorkPriorityCell {
+    dweller: Entity,
+    category: WorkCategory,
+}
+
+#[derive(Component)]
+pub struct HaulPriorityText;
+
+#[derive(Component)]
+pub struct HuntDistanceText;
+
+fn work_priority_label(priority: Option<u8>) -> String {
+    priority.map_or_else(|| "-".to_string(), |priority| priority.to_string())
+}
+
+pub fn toggle_work_priorities_ui(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    q_work_priorities_ui: Query<Entity, With<WorkPrioritiesUi>>,
+    q_dwellers: Query<(Entity, &Dweller)>,
+    haul_priority: Res<HaulPriority>,
+    hunt_distance: Res<HuntDistance>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyP) {
+        return;
+    }
+
+    if let Some(window) = q_work_priorities_ui.iter().next() {
+        commands.entity(window).despawn_recursive();
+        return;
+    }
+
+    let mut dwellers = q_dwellers.iter().collect::<Vec<_>>();
+    dwellers.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name));
+
+    commands
+        .spawn((WorkPrioritiesUi, UiWindow))
+        .with_children(|c| {
+            // Header: work categories
+            c.spawn(Node {
+                align_items: AlignItems::Center,
+                ..default()
+            })
+            .with_children(|c| {
+                c.spawn(Node {
+                    width: Val::Px(NAME_WIDTH),
+                    ..default()
+                });
+
+                for category in WorkCategory::ALL {
+                    c.spawn((
+                        Text::new(category.to_string()),
+                        Node {
+                            width: Val::Px(CELL_WIDTH),
+                            ..default()
+                        },
+                    ));
+                }
+            });
+
+            // One row per dweller, click a cell to cycle its priority
+            for (entity, dweller) in dwellers {
+                c.spawn(Node {
+                    align_items: AlignItems::Center,
+                    ..default()
+                })
+                .with_children(|c| {
+                    c.spawn((
+                        Text::new(dweller.name.clone()),
+                        Node {
+                            width: Val::Px(NAME_WIDTH),
+                            ..default()
+                        },
+                    ));
+
+                    for category in WorkCategory::ALL {
+                        c.spawn((
+                            UiButton,
+                            WorkPriorityCell {
+                                dweller: entity,
+                                category,
+                            },
+                            Node {
+                                width: Val::Px(CELL_WIDTH),
+                                padding: UiRect::all(Val::Px(5.0)),
+                                border: UiRect::all(Val::Px(4.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                        ))
+                        .with_child(Text::new(work_priority_label(
+                            dweller.work_priorities.get(category),
+                        )))
+                        .observe(observe_work_priority_cell_click);
+                    }
+                });
+            }
+
+            // Task priority of hauling, shared by all dwellers
+            c.spawn(Node {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(5.0),
+                ..default()
+            })
+            .with_children(|c| {
+                c.spawn((
+                    Text::new("Hauling task priority"),
+                    Node {
+                        width: Val::Px(NAME_WIDTH * 2.0),
+                        ..default()
+                    },
+                ));
+
+                for (label, delta) in [("-", -1), ("+", 1)] {
+                    c.spawn(UiButton).with_child(Text::new(label)).observe(
+                        move |_: Trigger<Pointer<Click>>,
+                              mut haul_priority: ResMut<HaulPriority>,
+                              mut q_texts: Query<&mut Text, With<HaulPriorityText>>| {
+                            haul_priority.0 += delta;
+
+                            for mut text in &mut q_texts {
+                                text.0 = haul_priority.0.to_string();
+                            }
+                        },
+                    );
+                }
+
+                c.spawn((HaulPriorityText, Text::new(haul_priority.0.to_string())));
+            });
+
+            // Hunts are abandoned when the prey fled this far
+            c.spawn(Node {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(5.0),
+                ..default()
+            })
+            .with_children(|c| {
+                c.spawn((
+                    Text::new("Hunt distance"),
+                    Node {
+                        width: Val::Px(NAME_WIDTH * 2.0),
+                        ..default()
+                    },
+                ));
+
+                for (label, delta) in [("-", -5), ("+", 5)] {
+                    c.spawn(UiButton).with_child(Text::new(label)).observe(
+                        move |_: Trigger<Pointer<Click>>,
+                              mut hunt_distance: ResMut<HuntDistance>,
+                              mut q_texts: Query<&mut Text, With<HuntDistanceText>>| {
+                            hunt_distance.0 = (hunt_distance.0 + delta).max(5);
+
+                            for mut text in &mut q_texts {
+                                text.0 = hunt_distance.0.to_string();
+                            }
+                        },
+                    );
+                }
+
+                c.spawn((HuntDistanceText, Text::new(hunt_distance.0.to_string())));
+            });
+        });
+}
+
+fn observe_work_priority_cell_click(
+    trigger: Trigger<Pointer<Click>>,
+    q_cells: Query<(&WorkPriorityCell, &Children)>,
+    mut q_dwellers: Query<&mut Dweller>,
+    mut q_texts: Query<&mut Text>,
+) {
+    let (cell, children) = extract_ok!(q_cells.get(trigger.entity()));
+    let mut dweller = extract_ok!(q_dwellers.get_mut(cell.dweller));
+
+    dweller.work_priorities.cycle(cell.category);
+
+    for child in children {
+        if let Ok(mut text) = q_texts.get_mut(*child) {
+            text.0 = work_priority_label(dweller.work_priorities.get(cell.category));
+        }
+    }
+}
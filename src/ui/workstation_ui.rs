@@ -3,8 +3,8 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use bevy::{prelude::*, utils::HashMap};
 
 use crate::{
-    data::WORKSTATIONS, extract_ok, tilemap_data::TilemapData, Task, TaskKind, TilePlaced,
-    BG_PRIMARY,
+    data::DEFINITIONS, extract_ok, tilemap_data::TilemapData, Bill, BillMode, ObjectsStock, Task,
+    TaskKind, TilePlaced, WorkstationBills, BG_PRIMARY,
 };
 
 #[derive(Event)]
@@ -27,9 +27,9 @@ pub struct UiBackground;
 #[derive(Component)]
 #[require(
     Node(|| Node {
-        width: Val::Px(200.),
-        justify_content: JustifyContent::SpaceBetween,
-        align_items: AlignItems::Center,
+        width: Val::Px(360.),
+        flex_direction: FlexDirection::Column,
+        row_gap: Val::Px(5.0),
         padding: UiRect::all(Val::Px(5.0)),
         border: UiRect::all(Val::Px(4.0)),
         ..default()
@@ -75,17 +75,14 @@ pub fn update_workstation_ui(
     asset_server: Res<AssetServer>,
     tilemap_data: Res<TilemapData>,
     q_workstation_ui: Query<(Entity, &WorkstationUi)>,
-    q_tasks: Query<&Task>,
-    mut changes: Local<HashMap<u128, u32>>,
+    stock: Res<ObjectsStock>,
+    q_tasks: Query<(&Task, &WorkstationBills)>,
+    mut changes: Local<HashMap<u128, (Vec<Bill>, Vec<u32>)>>,
 ) {
     for (ui_entity, workstation_ui) in &q_workstation_ui {
         let entity = workstation_ui.0;
 
-        let Ok(task) = q_tasks.get(entity) else {
-            continue;
-        };
-
-        let TaskKind::Workstation { amount } = task.kind else {
+        let Ok((task, bills)) = q_tasks.get(entity) else {
             continue;
         };
 
@@ -97,7 +94,7 @@ pub fn update_workstation_ui(
             continue;
         };
 
-        let Some(recipe) = WORKSTATIONS.get(&workstation) else {
+        let Some(recipes) = DEFINITIONS.workstations.get(&workstation) else {
             continue;
         };
 
@@ -105,63 +102,140 @@ pub fn update_workstation_ui(
             continue;
         };
 
-        if let Some(old_amount) = changes.get(&workstation_ui.1) {
-            if *old_amount == amount {
-                continue;
-            }
-        }
+        // Stock of each bill output, shown for UntilStock bills
+        let stocks = bills
+            .0
+            .iter()
+            .map(|bill| {
+                recipes
+                    .get(bill.recipe)
+                    .map_or(0, |recipe| stock.get(recipe.output))
+            })
+            .collect::<Vec<_>>();
 
-        changes.insert(workstation_ui.1, amount);
+        let state = (bills.0.clone(), stocks);
+        if changes.get(&workstation_ui.1) == Some(&state) {
+            continue;
+        }
+        let (_, stocks) = changes.entry(workstation_ui.1).insert(state).into_mut();
 
         ec.despawn_descendants().with_children(|c| {
-            // Minus button
-            c.spawn((
-                Button,
-                Node {
-                    padding: UiRect::all(Val::Px(5.0)),
-                    border: UiRect::all(Val::Px(4.0)),
-                    justify_content: JustifyContent::Center,
+            // Bills queue, the first one is crafted first
+            for (i, bill) in bills.0.iter().enumerate() {
+                let Some(recipe) = recipes.get(bill.recipe) else {
+                    continue;
+                };
+
+                c.spawn(Node {
+                    justify_content: JustifyContent::SpaceBetween,
                     align_items: AlignItems::Center,
                     ..default()
-                },
-            ))
-            .with_child((Text::new("-"), TextFont::from_font_size(20.0)))
-            .observe(
-                move |mut trigger: Trigger<Pointer<Click>>, mut q_tasks: Query<&mut Task>| {
-                    trigger.propagate(false);
-                    let mut task = extract_ok!(q_tasks.get_mut(entity));
-                    if let TaskKind::Workstation { ref mut amount, .. } = task.kind {
-                        *amount = amount.saturating_sub(1);
-                    }
-                },
-            );
-
-            c.spawn(ImageNode::new(
-                asset_server.load(recipe.0.data().sprite_path()),
-            ));
-            c.spawn(Text::new(format!("x{amount}")));
-
-            // Plus button
-            c.spawn((
-                Button,
-                Node {
-                    padding: UiRect::all(Val::Px(5.0)),
-                    border: UiRect::all(Val::Px(4.0)),
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    ..default()
-                },
-            ))
-            .with_child((Text::new("+"), TextFont::from_font_size(20.0)))
-            .observe(
-                move |mut trigger: Trigger<Pointer<Click>>, mut q_tasks: Query<&mut Task>| {
-                    trigger.propagate(false);
-                    let mut task = extract_ok!(q_tasks.get_mut(entity));
-                    if let TaskKind::Workstation { ref mut amount, .. } = task.kind {
-                        *amount = amount.saturating_add(1);
-                    }
-                },
-            );
+                })
+                .with_children(|c| {
+                    c.spawn(ImageNode::new(
+                        asset_server.load(recipe.output.data().sprite_path()),
+                    ));
+                    c.spawn(Text::new(match bill.mode {
+                        BillMode::Repeat => format!("x{}", bill.amount),
+                        BillMode::UntilStock => format!("{}/{}", stocks[i], bill.amount),
+                    }));
+
+                    spawn_bill_button(c, entity, i, "-", |bills, i| {
+                        if bills[i].amount > 1 {
+                            bills[i].amount -= 1;
+                        } else {
+                            bills.remove(i);
+                        }
+                    });
+                    spawn_bill_button(c, entity, i, "+", |bills, i| {
+                        bills[i].amount = bills[i].amount.saturating_add(1);
+                    });
+                    let next_mode = match bill.mode {
+                        BillMode::Repeat => "Until",
+                        BillMode::UntilStock => "Times",
+                    };
+                    spawn_bill_button(c, entity, i, next_mode, |bills, i| {
+                        bills[i].mode = match bills[i].mode {
+                            BillMode::Repeat => BillMode::UntilStock,
+                            BillMode::UntilStock => BillMode::Repeat,
+                        };
+                    });
+                    spawn_bill_button(c, entity, i, "^", |bills, i| {
+                        bills.swap(i, i.saturating_sub(1));
+                    });
+                    spawn_bill_button(c, entity, i, "x", |bills, i| {
+                        bills.remove(i);
+                    });
+                });
+            }
+
+            // New bills, one button per recipe
+            c.spawn(Node {
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(5.0),
+                ..default()
+            })
+            .with_children(|c| {
+                c.spawn(Text::new("Add"));
+
+                for (recipe_index, recipe) in recipes.iter().enumerate() {
+                    c.spawn((
+                        Button,
+                        Node {
+                            border: UiRect::all(Val::Px(4.0)),
+                            ..default()
+                        },
+                    ))
+                    .with_child(ImageNode::new(
+                        asset_server.load(recipe.output.data().sprite_path()),
+                    ))
+                    .observe(
+                        move |mut trigger: Trigger<Pointer<Click>>,
+                              mut q_bills: Query<&mut WorkstationBills>| {
+                            trigger.propagate(false);
+                            let mut bills = extract_ok!(q_bills.get_mut(entity));
+                            bills.0.push(Bill {
+                                recipe: recipe_index,
+                                amount: 1,
+                                mode: BillMode::Repeat,
+                            });
+                        },
+                    );
+                }
+            });
         });
     }
 }
+
+/// Button editing the bill `i` of the workstation task `entity`
+fn spawn_bill_button(
+    c: &mut ChildBuilder,
+    entity: Entity,
+    i: usize,
+    label: &str,
+    edit: impl Fn(&mut Vec<Bill>, usize) + Send + Sync + 'static,
+) {
+    c.spawn((
+        Button,
+        Node {
+            padding: UiRect::all(Val::Px(5.0)),
+            border: UiRect::all(Val::Px(4.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+    ))
+    .with_child((Text::new(label), TextFont::from_font_size(20.0)))
+    .observe(
+        move |mut trigger: Trigger<Pointer<Click>>, mut q_bills: Query<&mut WorkstationBills>| {
+            trigger.propagate(false);
+            let mut bills = extract_ok!(q_bills.get_mut(entity));
+
+            // The bill may have been completed since the UI was built
+            if i < bills.0.len() {
+                edit(&mut bills.0, i);
+            }
+        },
+    );
+}
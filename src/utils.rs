@@ -1,6 +1,7 @@
 use std::path::Path;
 
 use bevy::math::IVec2;
+use serde::{de::Visitor, Deserialize, Deserializer};
 
 #[macro_export]
 macro_rules! extract_ok {
@@ -22,32 +23,118 @@ macro_rules! extract_some {
     };
 }
 
+/// Id of a content definition, its data is loaded from `$field` in the definitions.
+/// The listed ids are the ones the code refers to, they come first and must be defined.
+/// The other definitions of the data file get the next ids, in the order of the file.
 #[macro_export]
-macro_rules! enum_map {
-    ($enum_name:ident => $data_type:ty {
-        $first_name:ident = $first_data:expr,
-        $( $name:ident = $data:expr ),* $(,)?
+macro_rules! define_ids {
+    ($id_name:ident => $data_type:ident in $field:ident {
+        $( $name:ident ),* $(,)?
     }) => {
-        #[derive(PartialEq, Eq, Hash, Clone, Copy, Reflect, Default, Encode, Decode, Debug)]
-        pub enum $enum_name {
-            #[default]
-            $first_name,
-            $(
-                $name,
-            )*
-        }
+        #[derive(PartialEq, Eq, Hash, Clone, Copy, Reflect, Default, Encode, Decode)]
+        pub struct $id_name(u16);
+
+        #[allow(non_upper_case_globals)]
+        impl $id_name {
+            $crate::define_ids!(@consts 0; $( $name, )*);
+
+            pub const BUILTIN: &[&str] = &[$( stringify!($name), )*];
+
+            /// Every defined id, the builtin ones first
+            pub fn all() -> impl Iterator<Item = Self> {
+                (0..$crate::data::NAMES.$field.len() as u16).map(Self)
+            }
+
+            pub fn from_name(name: &str) -> Option<Self> {
+                $crate::data::NAMES
+                    .$field
+                    .iter()
+                    .position(|n| n == name)
+                    .map(|i| Self(i as u16))
+            }
+
+            #[inline]
+            pub fn name(self) -> &'static str {
+                &$crate::data::NAMES.$field[self.index()]
+            }
 
-        impl $enum_name {
             #[inline]
-            pub fn data(&self) -> $data_type {
-                match self {
-                    Self::$first_name => $first_data,
-                    $(
-                        Self::$name => $data,
-                    )*
+            pub fn index(self) -> usize {
+                self.0 as usize
+            }
+
+            #[inline]
+            pub fn data(&self) -> &'static $data_type {
+                &$crate::data::DEFINITIONS.$field[self.index()]
+            }
+        }
+
+        impl std::fmt::Debug for $id_name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+
+        /// Written like a unit variant, as the ids were once enums
+        impl<'de> serde::Deserialize<'de> for $id_name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct IdVisitor;
+
+                impl<'de> serde::de::Visitor<'de> for IdVisitor {
+                    type Value = $id_name;
+
+                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        write!(f, "a name defined in {}.ron", stringify!($field))
+                    }
+
+                    fn visit_enum<A: serde::de::EnumAccess<'de>>(
+                        self,
+                        data: A,
+                    ) -> Result<Self::Value, A::Error> {
+                        use serde::de::{Error, VariantAccess};
+
+                        let ($crate::utils::Identifier(name), variant) = data.variant()?;
+                        variant.unit_variant()?;
+
+                        $id_name::from_name(&name).ok_or_else(|| {
+                            A::Error::custom(format!("{name} is not defined in {}.ron", stringify!($field)))
+                        })
+                    }
                 }
+
+                deserializer.deserialize_enum(stringify!($id_name), &[], IdVisitor)
             }
         }
+    };
+
+    (@consts $index:expr; $first:ident, $( $name:ident, )*) => {
+        pub const $first: Self = Self($index);
+        $crate::define_ids!(@consts $index + 1; $( $name, )*);
+    };
+
+    (@consts $index:expr;) => {};
+}
+
+/// Bare identifier, such as an enum variant name
+pub struct Identifier(pub String);
+
+impl<'de> Deserialize<'de> for Identifier {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct IdentifierVisitor;
+
+        impl Visitor<'_> for IdentifierVisitor {
+            type Value = Identifier;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("an identifier")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, name: &str) -> Result<Self::Value, E> {
+                Ok(Identifier(name.to_string()))
+            }
+        }
+
+        deserializer.deserialize_identifier(IdentifierVisitor)
     }
 }
 
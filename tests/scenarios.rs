@@ -0,0 +1,33 @@
+use std::path::Path;
+
+use dwellers::scenario::{install_error_counter, scenario_paths, Scenario};
+
+/// Every scenario of the scenarios directory passes
+#[test]
+fn scenarios() {
+    install_error_counter();
+
+    let paths = scenario_paths(Path::new("scenarios")).expect("Error while reading the scenarios");
+    assert!(!paths.is_empty(), "No scenarios found");
+
+    let failures = paths
+        .iter()
+        .filter_map(|path| {
+            let result = Scenario::load(path)
+                .map_err(|e| vec![e])
+                .and_then(|scenario| scenario.run());
+
+            result
+                .err()
+                .map(|failures| format!("{}:\n    {}", path.display(), failures.join("\n    ")))
+        })
+        .collect::<Vec<_>>();
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} scenarios failed:\n{}",
+        failures.len(),
+        paths.len(),
+        failures.join("\n")
+    );
+}